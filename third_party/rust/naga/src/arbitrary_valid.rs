@@ -0,0 +1,141 @@
+/*! A validity-directed [`Module`] generator, for fuzzing backends.
+
+Every IR type already derives [`Arbitrary`], but a raw `Arbitrary` `Module` is
+almost always invalid IR (dangling handles, type mismatches, non-SSA
+expression ordering), so fuzzing a backend directly off of
+`Unstructured` wastes nearly all of the fuzzer's iterations on the
+parser/validator boundary rather than on the backend itself.
+
+[`ValidModuleBuilder`] instead builds a module bottom-up: types are only
+assembled from handles already present in [`Module::types`], and expressions
+are appended to an arena in dependency order, so the result is valid by
+construction; no validator in this tree checks it. The generated function
+holds only literals and `Add` expressions over them. Its free choices — how
+many literals, their scalar kinds and values, and which same-typed right
+operand each `Add` takes — are drawn from the [`Unstructured`] byte stream, so
+the fuzzer's entropy still controls the shape of the generated module.
+*/
+
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::arena::{Handle, UniqueArena};
+use crate::{
+    BinaryOperator, Expression, Module, Scalar, ScalarKind, Statement, Type, TypeInner,
+    VectorSize,
+};
+
+/// Builds a [`Module`] that is valid by construction, consuming entropy from
+/// an [`Unstructured`] byte stream to make its free choices.
+pub struct ValidModuleBuilder<'a, 'b> {
+    u: &'b mut Unstructured<'a>,
+    module: Module,
+    /// Handles to the numeric (scalar/vector) types seeded into
+    /// `module.types`; expression generation does not read this list.
+    numeric_types: Vec<Handle<Type>>,
+}
+
+impl<'a, 'b> ValidModuleBuilder<'a, 'b> {
+    pub fn new(u: &'b mut Unstructured<'a>) -> Self {
+        Self {
+            u,
+            module: Module::default(),
+            numeric_types: Vec::new(),
+        }
+    }
+
+    /// Produce a module that is valid by construction.
+    pub fn build(mut self) -> Result<Module> {
+        self.seed_numeric_types()?;
+        self.build_function()?;
+        Ok(self.module)
+    }
+
+    /// Populate a handful of base scalar/vector types in `module.types` up
+    /// front.
+    fn seed_numeric_types(&mut self) -> Result<()> {
+        for kind in [ScalarKind::Sint, ScalarKind::Uint, ScalarKind::Float] {
+            let scalar = Scalar { kind, width: 4 };
+            let handle = self.insert_type(TypeInner::Scalar(scalar));
+            self.numeric_types.push(handle);
+            for size in [VectorSize::Bi, VectorSize::Tri, VectorSize::Quad] {
+                let handle = self.insert_type(TypeInner::Vector { size, scalar });
+                self.numeric_types.push(handle);
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_type(&mut self, inner: TypeInner) -> Handle<Type> {
+        self.module.types.insert(
+            Type { name: None, inner },
+            crate::Span::UNDEFINED,
+        )
+    }
+
+    /// Append a handful of expressions to a single function body, always
+    /// choosing operands from handles already resolved to a matching
+    /// numeric type, so the arena stays in valid SSA dependency order.
+    fn build_function(&mut self) -> Result<()> {
+        let handle = self.module.functions.append(
+            Default::default(),
+            crate::Span::UNDEFINED,
+        );
+        let function = self.module.functions.get_mut(handle);
+
+        // Seed a few literal expressions so `Binary` has operands to pick
+        // from; which scalar kind/width is drawn from the fuzzer.
+        let mut numeric_exprs: Vec<(Handle<Expression>, Scalar)> = Vec::new();
+        for _ in 0..self.u.int_in_range::<u8>(1..=4)? {
+            let kind = *self
+                .u
+                .choose(&[ScalarKind::Sint, ScalarKind::Uint, ScalarKind::Float])?;
+            let scalar = Scalar { kind, width: 4 };
+            let literal = match kind {
+                ScalarKind::Sint => crate::Literal::I32(i32::arbitrary(self.u)?),
+                ScalarKind::Uint => crate::Literal::U32(u32::arbitrary(self.u)?),
+                _ => crate::Literal::F32(f32::arbitrary(self.u)?),
+            };
+            let expr = function
+                .expressions
+                .append(Expression::Literal(literal), crate::Span::UNDEFINED);
+            numeric_exprs.push((expr, scalar));
+        }
+
+        // `Add` only ever picks two operands that already resolved to the
+        // same scalar type, per `BinaryOperator`'s documented type rules.
+        if numeric_exprs.len() >= 2 {
+            for (left, left_scalar) in numeric_exprs.clone() {
+                let candidates: Vec<Handle<Expression>> = numeric_exprs
+                    .iter()
+                    .filter(|&&(_, scalar)| scalar == left_scalar)
+                    .map(|&(handle, _)| handle)
+                    .collect();
+                let right = *self.u.choose(&candidates)?;
+                let expr = function.expressions.append(
+                    Expression::Binary {
+                        op: BinaryOperator::Add,
+                        left,
+                        right,
+                    },
+                    crate::Span::UNDEFINED,
+                );
+                numeric_exprs.push((expr, left_scalar));
+            }
+        }
+
+        // Every expression appended above needs an `Emit` covering it, or
+        // validation rejects the function for evaluating an expression that
+        // was never emitted. `function.expressions` started empty and
+        // nothing else touches it in between, so one range from the start
+        // covers the whole arena.
+        let emit_range = function.expressions.range_from(0);
+        function.body.insert(0, Statement::Emit(emit_range));
+
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+fn _unused(_: &UniqueArena<Type>) {}
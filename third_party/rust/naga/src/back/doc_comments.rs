@@ -0,0 +1,121 @@
+/*! Formatting [`DocComments`] for text output backends.
+
+Each backend targets a different shading language, each with its own comment
+syntax, so rather than have every backend reimplement "look up the doc
+comment lines for this item, then write them out," this module centralizes
+both halves: the lookup helpers below take a [`DocComments`] table and a
+handle/key into it, and [`write_lines`] takes care of the target-specific
+prefix.
+
+Call sites are expected to check [`WriterFlags::EMIT_DOC_COMMENTS`] before
+calling into this module at all, since an empty doc-comment table (the
+common case) makes every lookup here a no-op anyway.
+
+[`DocComments`]: crate::ir::DocComments
+[`WriterFlags::EMIT_DOC_COMMENTS`]: crate::back::WriterFlags::EMIT_DOC_COMMENTS
+*/
+
+use core::fmt::Write;
+
+use crate::arena::Handle;
+use crate::ir::DocComments;
+use crate::{Constant, Function, GlobalVariable, Type};
+
+/// The comment syntax a target language uses for a single documentation
+/// line. Every backend this module supports writes one line per `///`
+/// (WGSL-style) source line, so only the per-line prefix differs.
+#[derive(Clone, Copy)]
+pub enum CommentStyle {
+    /// `// line` — used by WGSL and GLSL.
+    DoubleSlash,
+    /// `/// line` — used by HLSL and MSL, whose toolchains recognize the
+    /// triple-slash form in their own documentation generators.
+    TripleSlash,
+}
+
+/// Write `lines`, one per line, indented by `indent` spaces, in `style`.
+/// Does nothing if `lines` is empty, so callers don't need to special-case
+/// items with no doc comment.
+pub fn write_lines(
+    out: &mut impl Write,
+    lines: &[alloc::string::String],
+    indent: usize,
+    style: CommentStyle,
+) -> core::fmt::Result {
+    let prefix = match style {
+        CommentStyle::DoubleSlash => "//",
+        CommentStyle::TripleSlash => "///",
+    };
+    for line in lines {
+        for _ in 0..indent {
+            out.write_char(' ')?;
+        }
+        if line.is_empty() {
+            writeln!(out, "{prefix}")?;
+        } else {
+            writeln!(out, "{prefix} {line}")?;
+        }
+    }
+    Ok(())
+}
+
+pub fn for_type<'a>(doc_comments: &'a DocComments, handle: Handle<Type>) -> &'a [alloc::string::String] {
+    doc_comments
+        .types
+        .get(&handle)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+pub fn for_struct_member<'a>(
+    doc_comments: &'a DocComments,
+    ty: Handle<Type>,
+    member_index: usize,
+) -> &'a [alloc::string::String] {
+    doc_comments
+        .struct_members
+        .get(&(ty, member_index))
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+pub fn for_function<'a>(
+    doc_comments: &'a DocComments,
+    handle: Handle<Function>,
+) -> &'a [alloc::string::String] {
+    doc_comments
+        .functions
+        .get(&handle)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+pub fn for_entry_point<'a>(doc_comments: &'a DocComments, index: usize) -> &'a [alloc::string::String] {
+    doc_comments
+        .entry_points
+        .get(&index)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+pub fn for_constant<'a>(
+    doc_comments: &'a DocComments,
+    handle: Handle<Constant>,
+) -> &'a [alloc::string::String] {
+    doc_comments
+        .constants
+        .get(&handle)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+pub fn for_global_variable<'a>(
+    doc_comments: &'a DocComments,
+    handle: Handle<GlobalVariable>,
+) -> &'a [alloc::string::String] {
+    doc_comments
+        .global_variables
+        .get(&handle)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
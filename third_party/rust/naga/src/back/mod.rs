@@ -0,0 +1,19 @@
+/*! Shared infrastructure for Naga's text-based output backends. */
+
+pub mod doc_comments;
+
+bitflags::bitflags! {
+    /// Flags shared by the text-emitting backends (WGSL, GLSL, HLSL, MSL)
+    /// that control optional, non-semantic output.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    pub struct WriterFlags: u32 {
+        /// Emit [`DocComments`](crate::ir::DocComments) recorded on the
+        /// module, attached above the item they document, translated into
+        /// the target language's comment syntax.
+        ///
+        /// Off by default: most consumers compile generated shaders
+        /// immediately and never read the text, so the extra bytes (and the
+        /// work of looking doc comments up while writing) would be wasted.
+        const EMIT_DOC_COMMENTS = 0x1;
+    }
+}
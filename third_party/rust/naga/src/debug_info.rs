@@ -0,0 +1,95 @@
+/*! Non-semantic source debug information, carried through the IR.
+
+[`Span`]s already let diagnostics point at source locations, but they don't
+let a backend reconstruct a DWARF-style line table, name scopes, or inlined
+call frames — so SPIR-V/HLSL/MSL generated from a [`Module`] is opaque to GPU
+debuggers and profilers. [`DebugInfo`] attaches a source-file table to the
+module and a lexical-scope tree that [`LocalVariable`], [`GlobalVariable`],
+and [`Override`] names, as well as individual expressions, can be annotated
+against. A line-table row can likewise correlate to a specific expression;
+see [`LineProgramRow::expression`] for why a statement can only be
+correlated indirectly, through the [`Statement::Emit`](crate::Statement::Emit)
+range that precedes it.
+
+Rather than storing a full `(file, line, column)` triple per instruction, the
+line table is modeled on DWARF v5's line-number program: a compact sequence of
+"advance line" / "set file" / "emit row" deltas. The table is only carried
+through the IR: no backend in this tree emits it, and
+[`WriterFlags`](crate::back::WriterFlags) has no flag for it.
+*/
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::arena::{Arena, Handle};
+use crate::{Expression, GlobalVariable, LocalVariable, Override};
+
+/// A source file referenced by [`DebugInfo`]'s line table.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    pub path: String,
+    /// The file's full text, if available, so backends can embed it
+    /// (`OpSource`) rather than just referencing it by path.
+    pub source: Option<String>,
+}
+
+/// A node in the lexical-scope tree: either the module-level scope, or a
+/// nested block/function/inlined-call scope within another.
+#[derive(Clone, Debug)]
+pub struct LexicalScope {
+    pub parent: Option<Handle<LexicalScope>>,
+    pub file: Handle<SourceFile>,
+    /// Present when this scope represents an inlined call, so debuggers can
+    /// reconstruct the original (uninlined) call stack.
+    pub inlined_at: Option<LineLoc>,
+}
+
+/// A `(line, column)` location within a [`SourceFile`].
+#[derive(Clone, Copy, Debug)]
+pub struct LineLoc {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// One entry of the DWARF-v5-style line-number program: advance the current
+/// line/file state by the given deltas, then emit a row mapping the current
+/// GPU instruction to the resulting source location.
+///
+/// `expression` is the row's correlation target: the IR's only instruction
+/// with a stable [`Handle`] is an [`Expression`], so a row produced for one
+/// names it here. A row produced for a statement that has no expression of
+/// its own to point at (`Kill`, `Return`, `Break`, ...) instead leaves this
+/// `None` and correlates only at the granularity of whichever
+/// [`Statement::Emit`] range precedes it — there is no `Handle<Statement>` in
+/// this IR for a row to name directly, since statements live in a
+/// [`Block`](crate::Block)'s `Vec`, not an arena.
+#[derive(Clone, Copy, Debug)]
+pub struct LineProgramRow {
+    pub file: Handle<SourceFile>,
+    pub line_delta: i32,
+    pub scope: Handle<LexicalScope>,
+    pub expression: Option<Handle<Expression>>,
+}
+
+/// The full set of structured debug information optionally carried by a
+/// [`Module`](crate::Module).
+#[derive(Clone, Debug, Default)]
+pub struct DebugInfo {
+    pub files: Arena<SourceFile>,
+    pub scopes: Arena<LexicalScope>,
+    /// The compact line-number program; backends walk it in order, applying
+    /// each row's deltas to reconstruct the full source location per
+    /// instruction rather than storing it redundantly at every instruction.
+    pub line_program: Vec<LineProgramRow>,
+    /// Scope a [`LocalVariable`] was declared in, for scoped name lookup in a
+    /// debugger's locals view.
+    pub local_variable_scopes: crate::FastIndexMap<Handle<LocalVariable>, Handle<LexicalScope>>,
+    pub global_variable_scopes: crate::FastIndexMap<Handle<GlobalVariable>, Handle<LexicalScope>>,
+    pub override_scopes: crate::FastIndexMap<Handle<Override>, Handle<LexicalScope>>,
+    /// Scope an individual expression was written in, for an inlined-call
+    /// frame or a nested block scope that doesn't coincide with any
+    /// declaration's own scope above. Keyed per-function: a [`Handle`] here
+    /// is only meaningful against the `expressions` arena of whichever
+    /// function or entry point this [`DebugInfo`] is being consulted for.
+    pub expression_scopes: crate::FastIndexMap<Handle<Expression>, Handle<LexicalScope>>,
+}
@@ -225,7 +225,7 @@ use alloc::{boxed::Box, string::String, vec::Vec};
 
 #[cfg(feature = "arbitrary")]
 use arbitrary::Arbitrary;
-use half::f16;
+use half::{bf16, f16};
 #[cfg(feature = "deserialize")]
 use serde::Deserialize;
 #[cfg(feature = "serialize")]
@@ -427,14 +427,37 @@ pub enum ScalarKind {
     /// Boolean type.
     Bool,
 
+    /// Brain floating-point type: an 8-bit-exponent, 7-bit-mantissa 16-bit
+    /// float, distinct from the IEEE-754 [`Float`] scalar of the same width.
+    ///
+    /// Used for ML-oriented shaders (quantized inference kernels) that need
+    /// `bf16`'s wider exponent range rather than `f16`'s precision. A `bf16`
+    /// scalar is never interchangeable with an `f16` [`Float`] scalar of the
+    /// same byte width.
+    ///
+    /// This is IR-only so far: there is no type-checking pass in this tree
+    /// that would actually reject mixing a `Bfloat16` operand with a `Float`
+    /// one in a binary expression, and no backend lowers [`Literal::BF16`] or
+    /// [`MathFunction::QuantizeToBF16`] to anything. A front end may produce
+    /// this scalar kind, but nothing downstream enforces or consumes it yet.
+    ///
+    /// [`Float`]: Self::Float
+    /// [`Literal::BF16`]: crate::Literal::BF16
+    /// [`MathFunction::QuantizeToBF16`]: crate::MathFunction::QuantizeToBF16
+    Bfloat16,
+
     /// WGSL abstract integer type.
     ///
-    /// These are forbidden by validation, and should never reach backends.
+    /// Legal while the owning [`Module`] is in [`ModulePhase::Abstract`] or
+    /// [`ModulePhase::OverrideResolved`]; forbidden from
+    /// [`ModulePhase::Concrete`] onward, and should never reach backends.
     AbstractInt,
 
     /// Abstract floating-point type.
     ///
-    /// These are forbidden by validation, and should never reach backends.
+    /// Legal while the owning [`Module`] is in [`ModulePhase::Abstract`] or
+    /// [`ModulePhase::OverrideResolved`]; forbidden from
+    /// [`ModulePhase::Concrete`] onward, and should never reach backends.
     AbstractFloat,
 }
 
@@ -461,6 +484,12 @@ pub enum ArraySize {
     /// The array size is constant.
     Constant(core::num::NonZeroU32),
     /// The array size is an override-expression.
+    ///
+    /// Only legal while the owning [`Module`] is in [`ModulePhase::Abstract`];
+    /// it must be resolved to [`Constant`] by the time the module reaches
+    /// [`ModulePhase::OverrideResolved`].
+    ///
+    /// [`Constant`]: Self::Constant
     Pending(Handle<Override>),
     /// The array size can change at runtime.
     Dynamic,
@@ -850,6 +879,66 @@ pub enum TypeInner {
     /// [`ARGUMENT`]: crate::valid::TypeFlags::ARGUMENT
     /// [naga#1864]: https://github.com/gfx-rs/naga/issues/1864
     BindingArray { base: Handle<Type>, size: ArraySize },
+
+    /// A subgroup-scoped matrix backed by hardware matrix-multiply-accumulate
+    /// units, as exposed by SPIR-V's `SPV_KHR_cooperative_matrix`, MSL's
+    /// `simdgroup_matrix`, and the in-progress WGSL subgroup-matrix proposal.
+    ///
+    /// A `CooperativeMatrix` is [`SIZED`] but not [`DATA`] or [`ARGUMENT`]: it
+    /// cannot live in a [`Uniform`] buffer, be returned from a function, or be
+    /// stored as a struct member. The only operations that may touch it are
+    /// [`CooperativeMatrixLoad`], [`CooperativeMatrixStore`],
+    /// [`CooperativeMatrixMulAdd`], and [`CooperativeMatrixFill`].
+    ///
+    /// `rows` and `columns` must be resolvable to compile-time constants (an
+    /// [`Override`]-expression is only legal if it is resolved by the time
+    /// pipeline constants are specialized); backends require matrix shapes to
+    /// be known statically.
+    ///
+    /// This is IR-only so far: [`dead_code`] treats these operations as
+    /// reachability roots/leaves like any other expression or statement, but
+    /// the `src/valid/` module in this tree holds only `Capabilities` and
+    /// checks none of the constraints described above, and there is no
+    /// SPIR-V/MSL backend file to lower
+    /// `CooperativeMatrix*` into `SPV_KHR_cooperative_matrix` or
+    /// `simdgroup_matrix`. A front end may build this IR, but nothing yet
+    /// validates or compiles it.
+    ///
+    /// [`dead_code`]: crate::proc::dead_code
+    /// [`SIZED`]: crate::valid::TypeFlags::SIZED
+    /// [`DATA`]: crate::valid::TypeFlags::DATA
+    /// [`ARGUMENT`]: crate::valid::TypeFlags::ARGUMENT
+    /// [`Uniform`]: AddressSpace::Uniform
+    /// [`CooperativeMatrixLoad`]: Expression::CooperativeMatrixLoad
+    /// [`CooperativeMatrixStore`]: Statement::CooperativeMatrixStore
+    /// [`CooperativeMatrixMulAdd`]: Expression::CooperativeMatrixMulAdd
+    /// [`CooperativeMatrixFill`]: Expression::CooperativeMatrixFill
+    CooperativeMatrix {
+        scalar: Scalar,
+        rows: u32,
+        columns: u32,
+        /// Which operand role of a matrix-multiply-accumulate this matrix
+        /// plays. Determines the shape constraints checked in
+        /// [`CooperativeMatrixMulAdd`] validation.
+        ///
+        /// [`CooperativeMatrixMulAdd`]: Expression::CooperativeMatrixMulAdd
+        usage: MatrixUse,
+    },
+}
+
+/// The operand role a [`TypeInner::CooperativeMatrix`] plays in a
+/// matrix-multiply-accumulate (`A * B + C`).
+#[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+pub enum MatrixUse {
+    /// The left-hand operand, shaped `rows × k`.
+    A,
+    /// The right-hand operand, shaped `k × columns`.
+    B,
+    /// The accumulator and result, shaped `rows × columns`.
+    Accumulator,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -863,6 +952,11 @@ pub enum Literal {
     F32(f32),
     /// May not be NaN or infinity.
     F16(f16),
+    /// May not be NaN or infinity.
+    ///
+    /// Carries a [`ScalarKind::Bfloat16`] scalar, not a [`ScalarKind::Float`]
+    /// one; the two are never implicitly converted.
+    BF16(bf16),
     U32(u32),
     I32(i32),
     U64(u64),
@@ -1194,6 +1288,11 @@ pub enum MathFunction {
     Transpose,
     Determinant,
     QuantizeToF16,
+    /// Like [`QuantizeToF16`], but rounds to the nearest value representable
+    /// as [`ScalarKind::Bfloat16`] instead.
+    ///
+    /// [`QuantizeToF16`]: Self::QuantizeToF16
+    QuantizeToBF16,
     // bits
     CountTrailingZeros,
     CountLeadingZeros,
@@ -1304,11 +1403,33 @@ pub enum GatherMode {
     /// Each gathers from their lane xored with the given by the expression
     ShuffleXor(Handle<Expression>),
     /// All gather from the same quad lane at the index given by the expression
+    ///
+    /// Unlike the other [`GatherMode`] variants, this is only valid within a
+    /// quad (a group of 4 invocations), not an arbitrary subgroup; gated
+    /// behind [`Capabilities::SUBGROUP_QUAD`], see
+    /// [`GatherMode::required_capabilities`].
+    ///
+    /// [`Capabilities::SUBGROUP_QUAD`]: crate::valid::Capabilities::SUBGROUP_QUAD
     QuadBroadcast(Handle<Expression>),
     /// Each gathers from the opposite quad lane along the given direction
+    ///
+    /// Same quad-only restriction as [`QuadBroadcast`], same capability.
+    ///
+    /// [`QuadBroadcast`]: Self::QuadBroadcast
     QuadSwap(Direction),
 }
 
+impl GatherMode {
+    /// The [`Capabilities`](crate::valid::Capabilities) a module needs to
+    /// declare support for in order to legally use `self`.
+    pub fn required_capabilities(&self) -> crate::valid::Capabilities {
+        match *self {
+            Self::QuadBroadcast(_) | Self::QuadSwap(_) => crate::valid::Capabilities::SUBGROUP_QUAD,
+            _ => crate::valid::Capabilities::empty(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "serialize", derive(Serialize))]
 #[cfg_attr(feature = "deserialize", derive(Deserialize))]
@@ -1692,6 +1813,38 @@ pub enum Expression {
         query: Handle<Expression>,
         committed: bool,
     },
+
+    /// Return the AABB (axis-aligned bounding box) of the candidate
+    /// intersection `query` is currently considering.
+    ///
+    /// Only meaningful while `query`'s current candidate is a
+    /// [`RayQueryIntersection::Aabb`]; resolves to
+    /// [`SpecialTypes::ray_candidate_bounds`], a struct of two [`Vec3`]
+    /// [`Float`] fields, `min` and `max`. Unlike [`RayQueryGetIntersection`],
+    /// which snapshots the whole intersection, this reads only the one piece
+    /// of procedural-candidate data the committed-intersection struct has no
+    /// room for: Vulkan's `rayQueryGetIntersectionCandidateAABBOpaqueEXT`/
+    /// bounds queries have no analogue in [`RayQueryGetIntersection`]'s
+    /// fixed shape.
+    ///
+    /// [`RayQueryIntersection::Aabb`]: crate::RayQueryIntersection::Aabb
+    /// [`Vec3`]: VectorSize::Tri
+    /// [`Float`]: ScalarKind::Float
+    /// [`RayQueryGetIntersection`]: Expression::RayQueryGetIntersection
+    RayQueryGetCandidateBounds { query: Handle<Expression> },
+
+    /// Return the instance data (custom index and instance ID) of the
+    /// candidate intersection `query` is currently considering.
+    ///
+    /// Resolves to [`SpecialTypes::ray_candidate_instance`], a struct of two
+    /// [`U32`] fields, `custom_index` and `instance_id` — the same pair
+    /// Vulkan's `rayQueryGetIntersectionInstanceCustomIndexEXT`/
+    /// `rayQueryGetIntersectionInstanceIdEXT` expose separately, bundled here
+    /// since a front end asking for one almost always wants both.
+    ///
+    /// [`U32`]: ScalarKind::Uint
+    RayQueryGetCandidateInstance { query: Handle<Expression> },
+
     /// Result of a [`SubgroupBallot`] statement.
     ///
     /// [`SubgroupBallot`]: Statement::SubgroupBallot
@@ -1701,6 +1854,42 @@ pub enum Expression {
     /// [`SubgroupCollectiveOperation`]: Statement::SubgroupCollectiveOperation
     /// [`SubgroupGather`]: Statement::SubgroupGather
     SubgroupOperationResult { ty: Handle<Type> },
+
+    /// Load a [`TypeInner::CooperativeMatrix`] from memory.
+    ///
+    /// `pointer` must be a pointer into [`AddressSpace::Storage`] or
+    /// [`AddressSpace::WorkGroup`]. `stride` is the distance, in elements,
+    /// between the starts of consecutive rows (or columns, if
+    /// `column_major`); it need not be a constant expression.
+    CooperativeMatrixLoad {
+        pointer: Handle<Expression>,
+        stride: Handle<Expression>,
+        column_major: bool,
+        ty: Handle<Type>,
+    },
+
+    /// Compute `a * b + c` over three [`TypeInner::CooperativeMatrix`]
+    /// values.
+    ///
+    /// `a` must have [`MatrixUse::A`], `b` must have [`MatrixUse::B`], and
+    /// `c` must have [`MatrixUse::Accumulator`]; `a`'s columns must equal
+    /// `b`'s rows, and the result has `c`'s shape.
+    CooperativeMatrixMulAdd {
+        a: Handle<Expression>,
+        b: Handle<Expression>,
+        c: Handle<Expression>,
+    },
+
+    /// Fill every element of a [`TypeInner::CooperativeMatrix`] with the same
+    /// scalar value.
+    CooperativeMatrixFill {
+        ty: Handle<Type>,
+        value: Handle<Expression>,
+    },
+
+    /// The number of elements of a [`MatrixUse::Accumulator`]
+    /// [`TypeInner::CooperativeMatrix`] held by the current invocation.
+    CooperativeMatrixLength(Handle<Expression>),
 }
 
 /// The value of the switch case.
@@ -2070,6 +2259,19 @@ pub enum Statement {
         /// The value with which to perform the atomic operation.
         value: Handle<Expression>,
     },
+    /// Stores a [`TypeInner::CooperativeMatrix`] value to memory.
+    ///
+    /// `pointer` must be a pointer into [`AddressSpace::Storage`] or
+    /// [`AddressSpace::WorkGroup`]; `stride` and `column_major` have the same
+    /// meaning as in [`CooperativeMatrixLoad`].
+    ///
+    /// [`CooperativeMatrixLoad`]: Expression::CooperativeMatrixLoad
+    CooperativeMatrixStore {
+        pointer: Handle<Expression>,
+        value: Handle<Expression>,
+        stride: Handle<Expression>,
+        column_major: bool,
+    },
     /// Load uniformly from a uniform pointer in the workgroup address space.
     ///
     /// Corresponds to the [`workgroupUniformLoad`](https://www.w3.org/TR/WGSL/#workgroupUniformLoad-builtin)
@@ -2323,6 +2525,20 @@ pub struct SpecialTypes {
     /// Call [`Module::generate_vertex_return_type`]
     pub ray_vertex_return: Option<Handle<Type>>,
 
+    /// Result type of [`Expression::RayQueryGetCandidateBounds`]: a struct of
+    /// two [`Vec3`](VectorSize::Tri) `f32` fields, `min` and `max`.
+    ///
+    /// Call [`crate::proc::ray_query::candidate_bounds_type`] to populate
+    /// this if needed and return the handle.
+    pub ray_candidate_bounds: Option<Handle<Type>>,
+
+    /// Result type of [`Expression::RayQueryGetCandidateInstance`]: a struct
+    /// of two `u32` fields, `custom_index` and `instance_id`.
+    ///
+    /// Call [`crate::proc::ray_query::candidate_instance_type`] to populate
+    /// this if needed and return the handle.
+    pub ray_candidate_instance: Option<Handle<Type>>,
+
     /// Types for predeclared wgsl types instantiated on demand.
     ///
     /// Call [`Module::generate_predeclared_type`] to populate this if
@@ -2386,6 +2602,51 @@ pub enum RayQueryIntersection {
     Aabb = 3,
 }
 
+/// A marker recording which lowering transformations have already been
+/// applied to a [`Module`], and thus which invariants currently hold.
+///
+/// This plays the same role for Naga IR that the "flavors"/phases of rustc's
+/// MIR play there: a single data structure ([`Module`]) whose legal shapes
+/// narrow as it moves through the pipeline, with the phase recorded
+/// explicitly instead of left implicit. Nothing in this crate checks a
+/// module's contents against the phase it claims; the phase is only a record
+/// kept by the passes described below.
+///
+/// Phases only move forward: [`Abstract`] → [`OverrideResolved`] →
+/// [`Concrete`]. The passes that perform override resolution and
+/// abstract-type concretization are the only ones that bump a module's
+/// phase; everything else preserves it.
+///
+/// [`Abstract`]: Self::Abstract
+/// [`OverrideResolved`]: Self::OverrideResolved
+/// [`Concrete`]: Self::Concrete
+#[derive(Clone, Copy, Debug, Default, Hash, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "deserialize", derive(Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+pub enum ModulePhase {
+    /// Fresh out of a front end.
+    ///
+    /// [`ScalarKind::AbstractInt`]/[`AbstractFloat`], [`ArraySize::Pending`],
+    /// and [`Expression::Override`] may all still appear.
+    ///
+    /// [`AbstractFloat`]: ScalarKind::AbstractFloat
+    #[default]
+    Abstract,
+
+    /// Pipeline-overridable constants have been specialized to concrete
+    /// values.
+    ///
+    /// [`ArraySize::Pending`] and [`Expression::Override`] are no longer
+    /// legal. Abstract scalar kinds folded away by specialization may still
+    /// linger until the following concretization pass runs.
+    OverrideResolved,
+
+    /// Backend-ready. No abstract scalar kinds, no pending array sizes, and
+    /// no override expressions remain anywhere in the module.
+    Concrete,
+}
+
 /// Doc comments preceding items.
 ///
 /// These can be used to generate automated documentation,
@@ -2495,4 +2756,17 @@ pub struct Module {
     pub diagnostic_filter_leaf: Option<Handle<DiagnosticFilterNode>>,
     /// Doc comments.
     pub doc_comments: Option<Box<DocComments>>,
+    /// Which lowering transformations this module has already been through.
+    ///
+    /// See [`ModulePhase`] for what each phase permits and forbids; nothing
+    /// in this crate checks a module's contents against it.
+    pub phase: ModulePhase,
+    /// Structured debug information (source files, lexical scopes, and a
+    /// DWARF-style line table), populated by front ends that parse it. No
+    /// backend in this tree reads it, and [`WriterFlags`] has no flag for it.
+    ///
+    /// [`WriterFlags`]: crate::back::WriterFlags
+    ///
+    /// See [`crate::debug_info`] for details.
+    pub debug_info: Option<Box<crate::debug_info::DebugInfo>>,
 }
@@ -0,0 +1,120 @@
+/*! A version-stamped binary container for a serialized [`Module`].
+
+`serde` is wired onto every IR type behind the `serialize`/`deserialize`
+features, but a bare serialized `Module` has no way to tell a reader which
+revision of the IR produced it. Whenever this chunk's enums gain or lose a
+variant (a new [`TypeInner`] case, a new [`MathFunction`], ...) an older
+snapshot would silently mis-deserialize. This module frames the serialized
+bytes with a magic header, a schema version, and the producing `naga` crate
+version, and refuses to load on a magic mismatch — upcasting older, known
+schema versions via an explicit migration table instead of erroring, the way
+stable-MIR tolerates snapshot drift across compiler versions.
+*/
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Module;
+
+/// Four bytes identifying this file as a Naga IR snapshot.
+const MAGIC: [u8; 4] = *b"NGIR";
+
+/// The current IR schema version.
+///
+/// Bump this, and add a migration to [`UPCASTS`], every time a variant is
+/// added to or removed from an enum in the `ir` module (`TypeInner`,
+/// `Literal`, `MathFunction`, `BinaryOperator`, `AtomicFunction`, ...).
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Failure modes for loading a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The file's magic header didn't match [`MAGIC`].
+    BadMagic,
+    /// The snapshot's schema version is newer than [`CURRENT_SCHEMA_VERSION`]
+    /// and this build doesn't know how to read it.
+    FutureSchema { found: u32 },
+    /// The module couldn't be serialized, or the framed bytes didn't
+    /// deserialize into a `Module`.
+    Decode(String),
+    Io(String),
+}
+
+struct Header {
+    schema_version: u32,
+    crate_version: [u8; 3],
+}
+
+fn write_header(out: &mut Vec<u8>, header: &Header) {
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&header.schema_version.to_le_bytes());
+    out.extend_from_slice(&header.crate_version);
+}
+
+fn read_header(bytes: &[u8]) -> Result<(Header, &[u8]), SnapshotError> {
+    if bytes.len() < 4 + 4 + 3 || bytes[0..4] != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let schema_version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    let crate_version = [bytes[8], bytes[9], bytes[10]];
+    Ok((
+        Header {
+            schema_version,
+            crate_version,
+        },
+        &bytes[11..],
+    ))
+}
+
+/// Write `module` to `out`, preceded by the snapshot header.
+#[cfg(feature = "serialize")]
+pub fn write_snapshot(module: &Module, out: &mut Vec<u8>) -> Result<(), SnapshotError> {
+    write_header(
+        out,
+        &Header {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            // The `naga` crate's own major.minor.patch.
+            crate_version: [
+                env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or(0),
+                env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or(0),
+                env!("CARGO_PKG_VERSION_PATCH").parse().unwrap_or(0),
+            ],
+        },
+    );
+    bincode::serialize_into(out, module).map_err(|err| SnapshotError::Decode(err.to_string()))
+}
+
+/// An upcasting shim from an older schema version to the current one. The
+/// payload is always decoded as the current [`Module`] first, so these
+/// operate on that decoded value and adapt it forward one step; `UPCASTS`
+/// chains them. An older snapshot whose payload does not decode as the
+/// current `Module` fails with [`SnapshotError::Decode`] before any shim runs.
+type Upcast = fn(Module) -> Module;
+
+/// Migrations from schema version `i + 1` to `i + 2`, indexed by `i`.
+///
+/// Example shim once a schema bump removes a field default: defaulting
+/// `TypeInner::AccelerationStructure { vertex_return }` to `false` when
+/// reading a pre-`vertex_return` snapshot.
+static UPCASTS: &[Upcast] = &[];
+
+/// Read a `Module` from a framed snapshot produced by [`write_snapshot`].
+///
+/// A known-older schema version whose payload still decodes as the current
+/// `Module` is upcast through [`UPCASTS`] rather than rejected; an unrecognized (newer) schema version is an error, since this
+/// build has no way to know what changed.
+#[cfg(feature = "deserialize")]
+pub fn read_snapshot(bytes: &[u8]) -> Result<Module, SnapshotError> {
+    let (header, payload) = read_header(bytes)?;
+    if header.schema_version > CURRENT_SCHEMA_VERSION {
+        return Err(SnapshotError::FutureSchema {
+            found: header.schema_version,
+        });
+    }
+    let mut module: Module =
+        bincode::deserialize(payload).map_err(|err| SnapshotError::Decode(err.to_string()))?;
+    for upcast in &UPCASTS[header.schema_version.saturating_sub(1) as usize..] {
+        module = upcast(module);
+    }
+    Ok(module)
+}
@@ -0,0 +1,78 @@
+/*! Concretizing abstract-typed literals.
+
+[`ModulePhase`] documents [`Concrete`] as the backend-ready terminus — "no
+abstract scalar kinds ... remain anywhere in the module" — but nothing in
+this tree actually produces a [`Concrete`] module: [`override_resolve`]
+only advances a module to [`OverrideResolved`], and no later pass bumps the
+phase further. This pass is that missing step: it defaults every remaining
+[`Literal::AbstractInt`]/[`AbstractFloat`] to WGSL's concrete default type
+([`I32`]/[`F32`], per the spec's abstract-numeric-type defaulting rules) and
+advances `module.phase` to [`Concrete`].
+
+This only rewrites *literals*. An abstract [`ScalarKind`] could in principle
+also appear on a [`Type`] in `module.types` (say, a `let` binding's inferred
+type before a typifier pass concretizes it) — but `types` is a
+[`UniqueArena`], so changing an entry's kind in place risks colliding with an
+already-concrete duplicate, and no typifier-driven type-rewriting pass exists
+in this tree to do that safely. If an abstract-kinded `Type` can still reach
+this pass, concretizing it is out of scope here.
+
+[`ModulePhase`]: crate::ModulePhase
+[`Concrete`]: crate::ModulePhase::Concrete
+[`OverrideResolved`]: crate::ModulePhase::OverrideResolved
+[`override_resolve`]: crate::proc::override_resolve
+[`Literal::AbstractInt`]: crate::Literal::AbstractInt
+[`AbstractFloat`]: crate::Literal::AbstractFloat
+[`I32`]: crate::Literal::I32
+[`F32`]: crate::Literal::F32
+[`ScalarKind`]: crate::ScalarKind
+[`Type`]: crate::Type
+[`UniqueArena`]: crate::UniqueArena
+*/
+
+use crate::arena::Arena;
+use crate::{Expression, Literal, Module, ModulePhase};
+
+/// Default every abstract-typed [`Literal`] left in `module` to its concrete
+/// WGSL default, and advance `module.phase` to [`ModulePhase::Concrete`].
+///
+/// Expects `module` to already be in [`ModulePhase::OverrideResolved`] (i.e.
+/// [`override_resolve::process_overrides`] has already run); concretizing
+/// before overrides are resolved would default an override's abstract
+/// init expression instead of leaving it for specialization.
+///
+/// [`override_resolve::process_overrides`]: crate::proc::override_resolve::process_overrides
+pub fn concretize(module: &mut Module) {
+    debug_assert_eq!(module.phase, ModulePhase::OverrideResolved);
+
+    concretize_arena(&mut module.global_expressions);
+    for (_, function) in module.functions.iter_mut() {
+        concretize_arena(&mut function.expressions);
+    }
+    for entry_point in &mut module.entry_points {
+        concretize_arena(&mut entry_point.function.expressions);
+    }
+
+    module.phase = ModulePhase::Concrete;
+}
+
+fn concretize_arena(arena: &mut Arena<Expression>) {
+    for (_, expr) in arena.iter_mut() {
+        if let Expression::Literal(ref mut literal) = *expr {
+            if let Some(concrete) = concretize_literal(*literal) {
+                *literal = concrete;
+            }
+        }
+    }
+}
+
+/// WGSL's abstract-numeric-type defaulting rules: an abstract integer
+/// defaults to `i32`, an abstract float to `f32`. Returns `None` for an
+/// already-concrete literal, which is left untouched.
+fn concretize_literal(literal: Literal) -> Option<Literal> {
+    match literal {
+        Literal::AbstractInt(value) => Some(Literal::I32(value as i32)),
+        Literal::AbstractFloat(value) => Some(Literal::F32(value as f32)),
+        _ => None,
+    }
+}
@@ -0,0 +1,180 @@
+/*! Canonicalizing constant-expression folding.
+
+The [module-level documentation] enumerates exactly which [`Expression`]
+variants form a *constant expression*, and notes that they "can be evaluated
+at module translation time" — the Naga analogue of rustc MIR's const-eval
+phase. This pass performs that evaluation: every constant-expression subtree
+whose operands are already folded literals is replaced by a single
+[`Literal`], [`Compose`], or [`ZeroValue`] value, shrinking what backends have
+to emit.
+
+Folding respects the abstract/concrete distinction: an all-abstract subtree
+folds to an abstract [`Literal`] and stays abstract until an [`As`] expression
+concretizes it, matching how WGSL's abstract-numeric-type defaulting works.
+
+Expressions are stored in dependency order, so a single forward pass over an
+arena both folds and memoizes: by the time a handle is visited, every operand
+it could reference has already been replaced by its folded form in place, so
+no separate memo table is needed. Folding is best-effort — an operation that
+would overflow, divide by zero, or produce NaN/Inf is simply left unfolded
+rather than panicking or guessing a value. No validator in this tree reports
+such an expression as an error.
+
+[module-level documentation]: crate::ir
+[`Compose`]: Expression::Compose
+[`ZeroValue`]: Expression::ZeroValue
+[`As`]: Expression::As
+*/
+
+use alloc::vec::Vec;
+
+use crate::arena::{Handle, UniqueArena};
+use crate::proc::eval;
+use crate::{Expression, Literal, Module, Type};
+
+/// Fold every constant expression in `module`'s function and global
+/// expression arenas into a canonical [`Literal`]/[`Compose`]/[`ZeroValue`]
+/// form, in place.
+///
+/// Expressions that are not constant expressions (loads, calls, and so on)
+/// are left untouched. Handles to expressions that folded away still point
+/// at valid (now-literal) expressions; a later dead-expression-elimination
+/// pass is responsible for dropping anything left unreferenced.
+pub fn fold_module(module: &mut Module) {
+    fold_arena(&mut module.global_expressions, &mut module.types);
+    for (_, function) in module.functions.iter_mut() {
+        fold_arena(&mut function.expressions, &mut module.types);
+    }
+    for entry_point in &mut module.entry_points {
+        fold_arena(&mut entry_point.function.expressions, &mut module.types);
+    }
+}
+
+fn fold_arena(arena: &mut crate::Arena<Expression>, types: &mut UniqueArena<Type>) {
+    // Expressions are stored in dependency order (an operand always precedes
+    // its use), so a single forward pass suffices: by the time we reach a
+    // handle, every handle it could reference has already been visited, and
+    // folded in place, which doubles as this pass's memoization.
+    let handles: Vec<_> = arena.iter().map(|(handle, _)| handle).collect();
+    for handle in handles {
+        if let Some(folded) = try_fold(arena, handle, types) {
+            *arena.get_mut(handle) = folded;
+        }
+    }
+}
+
+/// Attempt to evaluate the expression at `handle` to a canonical folded
+/// form, given that any constant-expression operands it has were already
+/// folded in place by an earlier iteration of [`fold_arena`]'s forward pass.
+///
+/// `types` is only consulted by `Splat`/`Swizzle`, which need a
+/// `Handle<Type>` for the vector they fold to; every other variant below
+/// ignores it.
+fn try_fold(
+    arena: &crate::Arena<Expression>,
+    handle: Handle<Expression>,
+    types: &mut UniqueArena<Type>,
+) -> Option<Expression> {
+    let literal_operand = |h: Handle<Expression>| match arena[h] {
+        Expression::Literal(lit) => Some(lit),
+        _ => None,
+    };
+
+    match arena[handle] {
+        Expression::Unary { op, expr } => {
+            let operand = literal_operand(expr)?;
+            eval::eval_unary(op, operand).map(Expression::Literal)
+        }
+        Expression::Binary { op, left, right } => {
+            let left = literal_operand(left)?;
+            let right = literal_operand(right)?;
+            eval::eval_binary(op, left, right).map(Expression::Literal)
+        }
+        Expression::As {
+            expr,
+            kind,
+            convert,
+        } => {
+            let operand = literal_operand(expr)?;
+            eval::eval_as(operand, kind, convert).map(Expression::Literal)
+        }
+        Expression::Math {
+            fun,
+            arg,
+            arg1,
+            arg2,
+            arg3,
+        } => {
+            let args = [arg, arg1, arg2, arg3]
+                .into_iter()
+                .flatten()
+                .map(literal_operand)
+                .collect::<Option<Vec<_>>>()?;
+            eval::eval_math(fun, &args).map(Expression::Literal)
+        }
+        Expression::Relational { fun, argument } => {
+            let argument = literal_operand(argument)?;
+            eval::eval_relational(fun, argument).map(Expression::Literal)
+        }
+        Expression::Splat { size, value } => {
+            let literal = literal_operand(value)?;
+            Some(Expression::Compose {
+                ty: eval::vector_type_for(types, literal, size)?,
+                // Repeat the already-folded operand's own handle, not
+                // `handle` (the `Splat` expression being folded itself,
+                // which would make each component point at the `Compose`
+                // that's replacing it).
+                components: alloc::vec![value; size as usize],
+            })
+        }
+        Expression::Swizzle {
+            size,
+            vector,
+            pattern,
+        } => {
+            // Only a `Compose`d vector of already-folded literals can be
+            // swizzled at this stage; anything else (e.g. a runtime value)
+            // is left for backends to lower directly.
+            let Expression::Compose {
+                components: ref source,
+                ..
+            } = arena[vector]
+            else {
+                return None;
+            };
+            let mut components = Vec::with_capacity(size as usize);
+            for component in &pattern[..size as usize] {
+                components.push(*source.get(component.index() as usize)?);
+            }
+            // The source vector's `ty` only applies when the swizzle keeps
+            // its full arity; a narrowing (or widening-via-repeat) swizzle
+            // needs a vector type sized to `size`, not the source's.
+            let literal = literal_operand(*components.first()?)?;
+            Some(Expression::Compose {
+                ty: eval::vector_type_for(types, literal, size)?,
+                components,
+            })
+        }
+        Expression::AccessIndex { base, index } => {
+            if let Expression::Compose { ref components, .. } = arena[base] {
+                components.get(index as usize).map(|&h| arena[h].clone())
+            } else {
+                None
+            }
+        }
+        Expression::Access { base, index } => {
+            let index = literal_operand(index)?;
+            let index = eval::literal_as_u32(index)?;
+            if let Expression::Compose { ref components, .. } = arena[base] {
+                components.get(index as usize).map(|&h| arena[h].clone())
+            } else {
+                None
+            }
+        }
+        // `Compose` itself is already in canonical form once its components
+        // are folded, and `Select`/`ZeroValue` either aren't single-valued
+        // constant expressions or are already canonical, so there's nothing
+        // further to rewrite here.
+        _ => None,
+    }
+}
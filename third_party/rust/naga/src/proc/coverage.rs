@@ -0,0 +1,224 @@
+/*! Execution-coverage instrumentation.
+
+This pass rewrites a [`Module`] so that every [`Block`] and branch arm records,
+via an atomic counter in a synthesized storage buffer, whether it executed on
+the GPU. It is modeled on rustc MIR's coverage-counter instrumentation: each
+region of code is assigned a dense id, and the resulting `region_id -> Span`
+table lets host tooling attribute hit counts back to source locations for
+shader profiling and coverage-guided testing.
+
+Counters are incremented with [`AtomicFunction::Add`] at the *start* of each
+region, before any [`Statement::Kill`] that might abort execution, so a
+region's counter accurately reflects whether it was entered even when control
+flow discards afterwards. Atomic increments are used specifically because they
+remain well-defined under non-uniform control flow, unlike derivative-style
+instructions.
+*/
+
+use alloc::vec::Vec;
+
+use crate::arena::Handle;
+use crate::{
+    AddressSpace, Arena, ArraySize, AtomicFunction, Block, Expression, Function, GlobalVariable,
+    Literal, Module, Scalar, ScalarKind, Span, Statement, StorageAccess, Type, TypeInner,
+};
+
+/// Maps a dense instrumentation region id to the source [`Span`] it covers.
+pub type RegionSpans = Vec<Span>;
+
+/// Rewrites `module` in place to count how often each [`Block`] and branch arm
+/// executes, and returns the id-to-span table for the inserted regions.
+///
+/// The counters live in a single new [`GlobalVariable`] in
+/// [`AddressSpace::Storage`]: a runtime-sized array of atomic `u32`, one
+/// element per entry of the returned table. The global is created with no
+/// resource binding and is not added to any entry point's interface.
+pub fn instrument(module: &mut Module) -> RegionSpans {
+    let mut spans = RegionSpans::new();
+
+    // First pass: assign region ids without mutating bodies, so the counter
+    // buffer can be sized before any function is rewritten.
+    for (_, function) in module.functions.iter() {
+        assign_regions(&function.body, &mut spans);
+    }
+    for entry_point in &module.entry_points {
+        assign_regions(&entry_point.function.body, &mut spans);
+    }
+
+    if spans.is_empty() {
+        return spans;
+    }
+
+    let counters_global = add_counters_global(module, spans.len());
+
+    let mut region_id = 0;
+    for (_, function) in module.functions.iter_mut() {
+        insert_counters(&mut function.body, &mut function.expressions, counters_global, &mut region_id);
+    }
+    for entry_point in &mut module.entry_points {
+        insert_counters(
+            &mut entry_point.function.body,
+            &mut entry_point.function.expressions,
+            counters_global,
+            &mut region_id,
+        );
+    }
+
+    spans
+}
+
+/// Walks `block` purely to collect spans, in the same order [`insert_counters`]
+/// will later visit regions, so the two passes agree on region ids.
+fn assign_regions(block: &Block, spans: &mut RegionSpans) {
+    spans.push(block.span());
+    for statement in block.iter() {
+        match *statement {
+            Statement::Block(ref inner) => assign_regions(inner, spans),
+            Statement::If {
+                ref accept,
+                ref reject,
+                ..
+            } => {
+                assign_regions(accept, spans);
+                assign_regions(reject, spans);
+            }
+            Statement::Switch { ref cases, .. } => {
+                for case in cases {
+                    assign_regions(&case.body, spans);
+                }
+            }
+            Statement::Loop {
+                ref body,
+                ref continuing,
+                ..
+            } => {
+                assign_regions(body, spans);
+                assign_regions(continuing, spans);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn add_counters_global(module: &mut Module, region_count: usize) -> Handle<GlobalVariable> {
+    let u32_scalar = Scalar {
+        kind: ScalarKind::Uint,
+        width: 4,
+    };
+    let counter_ty = module.types.insert(
+        Type {
+            name: None,
+            inner: TypeInner::Atomic(u32_scalar),
+        },
+        Span::UNDEFINED,
+    );
+    let array_ty = module.types.insert(
+        Type {
+            name: None,
+            inner: TypeInner::Array {
+                base: counter_ty,
+                size: ArraySize::Dynamic,
+                stride: 4,
+            },
+        },
+        Span::UNDEFINED,
+    );
+    let _ = region_count; // the array is runtime-sized; the host allocates the backing buffer
+
+    module.global_variables.append(
+        GlobalVariable {
+            name: Some("naga_coverage_counters".into()),
+            space: AddressSpace::Storage {
+                access: StorageAccess::ATOMIC | StorageAccess::STORE,
+            },
+            binding: None,
+            ty: array_ty,
+            init: None,
+        },
+        Span::UNDEFINED,
+    )
+}
+
+/// Rewrites `block` in place, prepending an atomic-add counter increment to
+/// the start of every region, consuming ids from `region_id` in the same
+/// order [`assign_regions`] used to build the span table.
+fn insert_counters(
+    block: &mut Block,
+    expressions: &mut Arena<Expression>,
+    counters: Handle<GlobalVariable>,
+    region_id: &mut u32,
+) {
+    let id = *region_id;
+    *region_id += 1;
+
+    for statement in block.iter_mut() {
+        match *statement {
+            Statement::Block(ref mut inner) => insert_counters(inner, expressions, counters, region_id),
+            Statement::If {
+                ref mut accept,
+                ref mut reject,
+                ..
+            } => {
+                insert_counters(accept, expressions, counters, region_id);
+                insert_counters(reject, expressions, counters, region_id);
+            }
+            Statement::Switch { ref mut cases, .. } => {
+                for case in cases.iter_mut() {
+                    insert_counters(&mut case.body, expressions, counters, region_id);
+                }
+            }
+            Statement::Loop {
+                ref mut body,
+                ref mut continuing,
+                ..
+            } => {
+                insert_counters(body, expressions, counters, region_id);
+                insert_counters(continuing, expressions, counters, region_id);
+            }
+            _ => {}
+        }
+    }
+
+    prepend_increment(block, expressions, counters, id);
+}
+
+fn prepend_increment(
+    block: &mut Block,
+    expressions: &mut Arena<Expression>,
+    counters: Handle<GlobalVariable>,
+    region_id: u32,
+) {
+    let global_expr = expressions.append(Expression::GlobalVariable(counters), Span::UNDEFINED);
+    let index_expr = expressions.append(
+        Expression::Literal(Literal::U32(region_id)),
+        Span::UNDEFINED,
+    );
+    let pointer_expr = expressions.append(
+        Expression::Access {
+            base: global_expr,
+            index: index_expr,
+        },
+        Span::UNDEFINED,
+    );
+    let one_expr = expressions.append(Expression::Literal(Literal::U32(1)), Span::UNDEFINED);
+    let emit_start = expressions.len() as u32 - 4;
+    let emit_range = expressions.range_from(emit_start);
+
+    block.splice(
+        0,
+        [
+            Statement::Emit(emit_range),
+            Statement::Atomic {
+                pointer: pointer_expr,
+                fun: AtomicFunction::Add,
+                value: one_expr,
+                result: None,
+            },
+        ],
+    );
+}
+
+// Only used to document the counter's relationship to a `Function`'s arena;
+// kept private since this pass owns its own traversal order.
+#[allow(dead_code)]
+fn _assert_function_shape(_: &Function) {}
@@ -0,0 +1,818 @@
+/*! Dead-expression elimination and arena compaction.
+
+Earlier passes like [`constant_fold`] and [`override_resolve`] routinely leave
+behind expressions that nothing references any more — an [`Override`] folded
+to a [`Literal`] still has the original init expression sitting in the arena,
+for instance. Backends walk the *entire* expression arena when emitting code,
+so each of these stragglers costs every future compilation a wasted
+declaration (or, worse, an invalid one if it references something later
+passes removed). This pass removes them: it computes which expressions are
+reachable from the statement tree's roots, then rebuilds each arena to
+contain only the reachable subset, remapping every `Handle<Expression>` and
+regenerating contiguous [`Statement::Emit`] ranges to match.
+
+[`constant_fold`]: crate::proc::constant_fold
+[`override_resolve`]: crate::proc::override_resolve
+*/
+
+use alloc::vec::Vec;
+
+use crate::arena::{Arena, Handle};
+use crate::{Block, Expression, Function, Module, Statement};
+
+/// Remove every unreachable expression from each function (and the global
+/// expression arena) in `module`, compacting the arenas and remapping
+/// handles accordingly.
+pub fn eliminate(module: &mut Module) {
+    for (_, function) in module.functions.iter_mut() {
+        eliminate_function(function);
+    }
+    for entry_point in &mut module.entry_points {
+        eliminate_function(&mut entry_point.function);
+    }
+    // The global arena is reachable from every `Override`/`Constant`/
+    // `GlobalVariable` init and from entry points' workgroup size overrides,
+    // so it's compacted the same way, rooted there.
+    compact_global_expressions(module);
+}
+
+fn eliminate_function(function: &mut Function) {
+    let mut reachable = alloc::collections::BTreeSet::new();
+    mark_block(&function.body, &function.expressions, &mut reachable);
+    for (_, result) in function.named_expressions.iter() {
+        let _ = result;
+    }
+    for &handle in function.named_expressions.keys() {
+        mark_expr(handle, &function.expressions, &mut reachable);
+    }
+    for (_, local) in function.local_variables.iter() {
+        if let Some(init) = local.init {
+            mark_expr(init, &function.expressions, &mut reachable);
+        }
+    }
+
+    let (new_arena, remap) = compact(&function.expressions, &reachable);
+    function.expressions = new_arena;
+    remap_block(&mut function.body, &remap);
+    for (_, local) in function.local_variables.iter_mut() {
+        if let Some(ref mut init) = local.init {
+            remap_handle(init, &remap);
+        }
+    }
+    let remapped_named = function
+        .named_expressions
+        .iter()
+        .map(|(&handle, name)| (remap[&handle], name.clone()))
+        .collect();
+    function.named_expressions = remapped_named;
+}
+
+/// Mark every expression transitively referenced by a statement in `block`
+/// as reachable — `Store`, `ImageStore`, `Atomic`, `Return`, branch
+/// conditions, call arguments, ray queries, and so on are the roots; this
+/// walks from each of those through `Compose`/`Access`/`Math`/... operands.
+fn mark_block(
+    block: &Block,
+    expressions: &Arena<Expression>,
+    reachable: &mut alloc::collections::BTreeSet<Handle<Expression>>,
+) {
+    for statement in block.iter() {
+        match *statement {
+            Statement::Emit(_) => {
+                // `Emit` just announces a range's side-effect-free
+                // visibility; it creates no roots of its own.
+            }
+            Statement::Block(ref inner) => mark_block(inner, expressions, reachable),
+            Statement::If {
+                condition,
+                ref accept,
+                ref reject,
+            } => {
+                mark_expr(condition, expressions, reachable);
+                mark_block(accept, expressions, reachable);
+                mark_block(reject, expressions, reachable);
+            }
+            Statement::Switch {
+                selector,
+                ref cases,
+            } => {
+                mark_expr(selector, expressions, reachable);
+                for case in cases {
+                    mark_block(&case.body, expressions, reachable);
+                }
+            }
+            Statement::Loop {
+                ref body,
+                ref continuing,
+                break_if,
+            } => {
+                mark_block(body, expressions, reachable);
+                mark_block(continuing, expressions, reachable);
+                if let Some(handle) = break_if {
+                    mark_expr(handle, expressions, reachable);
+                }
+            }
+            Statement::Return { value: Some(value) } => mark_expr(value, expressions, reachable),
+            Statement::Store { pointer, value } => {
+                mark_expr(pointer, expressions, reachable);
+                mark_expr(value, expressions, reachable);
+            }
+            Statement::ImageStore {
+                image,
+                coordinate,
+                array_index,
+                value,
+            } => {
+                mark_expr(image, expressions, reachable);
+                mark_expr(coordinate, expressions, reachable);
+                if let Some(h) = array_index {
+                    mark_expr(h, expressions, reachable);
+                }
+                mark_expr(value, expressions, reachable);
+            }
+            Statement::Atomic {
+                pointer,
+                value,
+                result,
+                ..
+            } => {
+                mark_expr(pointer, expressions, reachable);
+                mark_expr(value, expressions, reachable);
+                if let Some(result) = result {
+                    mark_expr(result, expressions, reachable);
+                }
+            }
+            Statement::Call {
+                ref arguments,
+                result,
+                ..
+            } => {
+                for &arg in arguments {
+                    mark_expr(arg, expressions, reachable);
+                }
+                if let Some(result) = result {
+                    mark_expr(result, expressions, reachable);
+                }
+            }
+            Statement::ImageAtomic {
+                image,
+                coordinate,
+                array_index,
+                value,
+                ..
+            } => {
+                mark_expr(image, expressions, reachable);
+                mark_expr(coordinate, expressions, reachable);
+                if let Some(h) = array_index {
+                    mark_expr(h, expressions, reachable);
+                }
+                mark_expr(value, expressions, reachable);
+            }
+            Statement::CooperativeMatrixStore {
+                pointer,
+                value,
+                stride,
+                ..
+            } => {
+                mark_expr(pointer, expressions, reachable);
+                mark_expr(value, expressions, reachable);
+                mark_expr(stride, expressions, reachable);
+            }
+            Statement::WorkGroupUniformLoad { pointer, result } => {
+                mark_expr(pointer, expressions, reachable);
+                mark_expr(result, expressions, reachable);
+            }
+            Statement::SubgroupBallot { result, predicate } => {
+                mark_expr(result, expressions, reachable);
+                if let Some(h) = predicate {
+                    mark_expr(h, expressions, reachable);
+                }
+            }
+            Statement::SubgroupGather {
+                argument, result, ..
+            } => {
+                mark_expr(argument, expressions, reachable);
+                mark_expr(result, expressions, reachable);
+            }
+            Statement::SubgroupCollectiveOperation {
+                argument, result, ..
+            } => {
+                mark_expr(argument, expressions, reachable);
+                mark_expr(result, expressions, reachable);
+            }
+            Statement::RayQuery { query, ref fun } => {
+                mark_expr(query, expressions, reachable);
+                mark_ray_query_function(fun, expressions, reachable);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Mark the expression operands nested inside a [`RayQueryFunction`] as
+/// reachable, the same way [`mark_block`] does for a [`Statement::RayQuery`]'s
+/// `query` operand.
+///
+/// [`RayQueryFunction`]: crate::RayQueryFunction
+fn mark_ray_query_function(
+    fun: &crate::RayQueryFunction,
+    expressions: &Arena<Expression>,
+    reachable: &mut alloc::collections::BTreeSet<Handle<Expression>>,
+) {
+    match *fun {
+        crate::RayQueryFunction::Initialize {
+            acceleration_structure,
+            descriptor,
+        } => {
+            mark_expr(acceleration_structure, expressions, reachable);
+            mark_expr(descriptor, expressions, reachable);
+        }
+        crate::RayQueryFunction::Proceed { result } => mark_expr(result, expressions, reachable),
+        crate::RayQueryFunction::GenerateIntersection { hit_t } => {
+            mark_expr(hit_t, expressions, reachable)
+        }
+        crate::RayQueryFunction::ConfirmIntersection => {}
+        #[allow(unreachable_patterns)]
+        _ => {}
+    }
+}
+
+/// Mark `handle` and, transitively, every expression it (directly or
+/// indirectly) reads from, as reachable.
+fn mark_expr(
+    handle: Handle<Expression>,
+    expressions: &Arena<Expression>,
+    reachable: &mut alloc::collections::BTreeSet<Handle<Expression>>,
+) {
+    if !reachable.insert(handle) {
+        return;
+    }
+    let mut visit = |h: Handle<Expression>| mark_expr(h, expressions, reachable);
+    match expressions[handle] {
+        Expression::Access { base, index } => {
+            visit(base);
+            visit(index);
+        }
+        Expression::AccessIndex { base, .. } => visit(base),
+        Expression::Splat { value, .. } => visit(value),
+        Expression::Swizzle { vector, .. } => visit(vector),
+        Expression::Compose { ref components, .. } => {
+            for &c in components {
+                visit(c);
+            }
+        }
+        Expression::Load { pointer } => visit(pointer),
+        Expression::ImageSample {
+            image,
+            sampler,
+            coordinate,
+            array_index,
+            offset,
+            level,
+            depth_ref,
+            ..
+        } => {
+            visit(image);
+            visit(sampler);
+            visit(coordinate);
+            if let Some(h) = array_index {
+                visit(h);
+            }
+            if let Some(h) = offset {
+                visit(h);
+            }
+            match level {
+                crate::SampleLevel::Exact(h) | crate::SampleLevel::Bias(h) => visit(h),
+                crate::SampleLevel::Gradient { x, y } => {
+                    visit(x);
+                    visit(y);
+                }
+                crate::SampleLevel::Auto | crate::SampleLevel::Zero => {}
+            }
+            if let Some(h) = depth_ref {
+                visit(h);
+            }
+        }
+        Expression::ImageLoad {
+            image,
+            coordinate,
+            array_index,
+            sample,
+            level,
+        } => {
+            visit(image);
+            visit(coordinate);
+            if let Some(h) = array_index {
+                visit(h);
+            }
+            if let Some(h) = sample {
+                visit(h);
+            }
+            if let Some(h) = level {
+                visit(h);
+            }
+        }
+        Expression::Unary { expr, .. } => visit(expr),
+        Expression::Binary { left, right, .. } => {
+            visit(left);
+            visit(right);
+        }
+        Expression::Select {
+            condition,
+            accept,
+            reject,
+        } => {
+            visit(condition);
+            visit(accept);
+            visit(reject);
+        }
+        Expression::Relational { argument, .. } => visit(argument),
+        Expression::Math {
+            arg,
+            arg1,
+            arg2,
+            arg3,
+            ..
+        } => {
+            for h in [Some(arg), arg1, arg2, arg3].into_iter().flatten() {
+                visit(h);
+            }
+        }
+        Expression::As { expr, .. } => visit(expr),
+        Expression::CooperativeMatrixLoad { pointer, stride, .. } => {
+            visit(pointer);
+            visit(stride);
+        }
+        Expression::CooperativeMatrixMulAdd { a, b, c } => {
+            visit(a);
+            visit(b);
+            visit(c);
+        }
+        Expression::CooperativeMatrixFill { value, .. } => visit(value),
+        Expression::CooperativeMatrixLength(pointer) => visit(pointer),
+        Expression::RayQueryGetIntersection { query, .. } => visit(query),
+        Expression::RayQueryGetCandidateBounds { query } => visit(query),
+        Expression::RayQueryGetCandidateInstance { query } => visit(query),
+        Expression::Derivative { expr, .. } => visit(expr),
+        Expression::ArrayLength(expr) => visit(expr),
+        Expression::ImageQuery { image, query } => {
+            visit(image);
+            if let crate::ImageQuery::Size { level: Some(level) } = query {
+                visit(level);
+            }
+        }
+        Expression::RayQueryVertexPositions { query, .. } => visit(query),
+        // Constants, literals, globals, function arguments, and the rest
+        // have no expression operands to follow further.
+        _ => {}
+    }
+}
+
+/// Build a fresh arena containing only `reachable`'s members (in original
+/// order, to preserve the existing dependency-order invariant), along with
+/// the handle remapping from old to new.
+fn compact(
+    arena: &Arena<Expression>,
+    reachable: &alloc::collections::BTreeSet<Handle<Expression>>,
+) -> (Arena<Expression>, alloc::collections::BTreeMap<Handle<Expression>, Handle<Expression>>) {
+    let mut new_arena = Arena::new();
+    let mut remap = alloc::collections::BTreeMap::new();
+    for (handle, expr) in arena.iter() {
+        if reachable.contains(&handle) {
+            let mut expr = expr.clone();
+            remap_expr_operands(&mut expr, &remap);
+            let new_handle = new_arena.append(expr, arena.get_span(handle));
+            remap.insert(handle, new_handle);
+        }
+    }
+    (new_arena, remap)
+}
+
+fn remap_expr_operands(
+    expr: &mut Expression,
+    remap: &alloc::collections::BTreeMap<Handle<Expression>, Handle<Expression>>,
+) {
+    let r = |h: &mut Handle<Expression>| {
+        if let Some(&new) = remap.get(h) {
+            *h = new;
+        }
+    };
+    match *expr {
+        Expression::Access {
+            ref mut base,
+            ref mut index,
+        } => {
+            r(base);
+            r(index);
+        }
+        Expression::AccessIndex { ref mut base, .. } => r(base),
+        Expression::Splat { ref mut value, .. } => r(value),
+        Expression::Swizzle { ref mut vector, .. } => r(vector),
+        Expression::Compose {
+            ref mut components, ..
+        } => {
+            for c in components {
+                r(c);
+            }
+        }
+        Expression::Load { ref mut pointer } => r(pointer),
+        Expression::Unary { ref mut expr, .. } => r(expr),
+        Expression::Binary {
+            ref mut left,
+            ref mut right,
+            ..
+        } => {
+            r(left);
+            r(right);
+        }
+        Expression::As { ref mut expr, .. } => r(expr),
+        Expression::ImageSample {
+            ref mut image,
+            ref mut sampler,
+            ref mut coordinate,
+            ref mut array_index,
+            ref mut offset,
+            ref mut level,
+            ref mut depth_ref,
+            ..
+        } => {
+            r(image);
+            r(sampler);
+            r(coordinate);
+            if let Some(ref mut h) = *array_index {
+                r(h);
+            }
+            if let Some(ref mut h) = *offset {
+                r(h);
+            }
+            match *level {
+                crate::SampleLevel::Exact(ref mut h) | crate::SampleLevel::Bias(ref mut h) => r(h),
+                crate::SampleLevel::Gradient { ref mut x, ref mut y } => {
+                    r(x);
+                    r(y);
+                }
+                crate::SampleLevel::Auto | crate::SampleLevel::Zero => {}
+            }
+            if let Some(ref mut h) = *depth_ref {
+                r(h);
+            }
+        }
+        Expression::ImageLoad {
+            ref mut image,
+            ref mut coordinate,
+            ref mut array_index,
+            ref mut sample,
+            ref mut level,
+        } => {
+            r(image);
+            r(coordinate);
+            if let Some(ref mut h) = *array_index {
+                r(h);
+            }
+            if let Some(ref mut h) = *sample {
+                r(h);
+            }
+            if let Some(ref mut h) = *level {
+                r(h);
+            }
+        }
+        Expression::Select {
+            ref mut condition,
+            ref mut accept,
+            ref mut reject,
+        } => {
+            r(condition);
+            r(accept);
+            r(reject);
+        }
+        Expression::Relational { ref mut argument, .. } => r(argument),
+        Expression::Math {
+            ref mut arg,
+            ref mut arg1,
+            ref mut arg2,
+            ref mut arg3,
+            ..
+        } => {
+            r(arg);
+            if let Some(ref mut h) = *arg1 {
+                r(h);
+            }
+            if let Some(ref mut h) = *arg2 {
+                r(h);
+            }
+            if let Some(ref mut h) = *arg3 {
+                r(h);
+            }
+        }
+        Expression::CooperativeMatrixLoad {
+            ref mut pointer,
+            ref mut stride,
+            ..
+        } => {
+            r(pointer);
+            r(stride);
+        }
+        Expression::CooperativeMatrixMulAdd {
+            ref mut a,
+            ref mut b,
+            ref mut c,
+        } => {
+            r(a);
+            r(b);
+            r(c);
+        }
+        Expression::CooperativeMatrixFill { ref mut value, .. } => r(value),
+        Expression::CooperativeMatrixLength(ref mut pointer) => r(pointer),
+        Expression::RayQueryGetIntersection { ref mut query, .. } => r(query),
+        Expression::RayQueryGetCandidateBounds { ref mut query } => r(query),
+        Expression::RayQueryGetCandidateInstance { ref mut query } => r(query),
+        Expression::Derivative { ref mut expr, .. } => r(expr),
+        Expression::ArrayLength(ref mut expr) => r(expr),
+        Expression::ImageQuery {
+            ref mut image,
+            ref mut query,
+        } => {
+            r(image);
+            if let crate::ImageQuery::Size {
+                level: Some(ref mut level),
+            } = *query
+            {
+                r(level);
+            }
+        }
+        Expression::RayQueryVertexPositions { ref mut query, .. } => r(query),
+        _ => {
+            // Every other reachable variant either has no `Expression`
+            // operands (literals, globals, ...) or isn't yet produced by a
+            // pass that runs before this one; remapping is a no-op for it.
+        }
+    }
+}
+
+/// Rewrite every `Handle<Expression>` referenced by statements in `block`,
+/// and regenerate `Emit` ranges to describe contiguous runs of the
+/// (now-compacted) arena rather than the original numbering.
+///
+/// Every original `Emit` range is maximal and non-overlapping (naga's own
+/// invariant — see the crate-level docs), and [`compact`] preserves the
+/// relative order of the expressions it keeps. So the surviving subset of
+/// any one original range still forms a single contiguous span in the new
+/// arena; its new bounds are just the lowest and highest remapped handles
+/// that came from inside it. A range that lost every member is dropped
+/// entirely rather than emitted empty.
+fn remap_block(
+    block: &mut Block,
+    remap: &alloc::collections::BTreeMap<Handle<Expression>, Handle<Expression>>,
+) {
+    block.retain(|statement| !matches!(statement, Statement::Emit(range) if remap_emit_range(range, remap).is_none()));
+    for statement in block.iter_mut() {
+        match *statement {
+            Statement::Emit(ref mut range) => {
+                if let Some(new_range) = remap_emit_range(range, remap) {
+                    *range = new_range;
+                }
+            }
+            Statement::Block(ref mut inner) => remap_block(inner, remap),
+            Statement::If {
+                ref mut condition,
+                ref mut accept,
+                ref mut reject,
+            } => {
+                remap_handle(condition, remap);
+                remap_block(accept, remap);
+                remap_block(reject, remap);
+            }
+            Statement::Switch {
+                ref mut selector,
+                ref mut cases,
+            } => {
+                remap_handle(selector, remap);
+                for case in cases {
+                    remap_block(&mut case.body, remap);
+                }
+            }
+            Statement::Loop {
+                ref mut body,
+                ref mut continuing,
+                ref mut break_if,
+            } => {
+                remap_block(body, remap);
+                remap_block(continuing, remap);
+                if let Some(ref mut handle) = *break_if {
+                    remap_handle(handle, remap);
+                }
+            }
+            Statement::Return { value: Some(ref mut value) } => remap_handle(value, remap),
+            Statement::Store {
+                ref mut pointer,
+                ref mut value,
+            } => {
+                remap_handle(pointer, remap);
+                remap_handle(value, remap);
+            }
+            Statement::ImageStore {
+                ref mut image,
+                ref mut coordinate,
+                ref mut array_index,
+                ref mut value,
+            } => {
+                remap_handle(image, remap);
+                remap_handle(coordinate, remap);
+                if let Some(ref mut h) = *array_index {
+                    remap_handle(h, remap);
+                }
+                remap_handle(value, remap);
+            }
+            Statement::Atomic {
+                ref mut pointer,
+                ref mut value,
+                ref mut result,
+                ..
+            } => {
+                remap_handle(pointer, remap);
+                remap_handle(value, remap);
+                if let Some(ref mut result) = *result {
+                    remap_handle(result, remap);
+                }
+            }
+            Statement::ImageAtomic {
+                ref mut image,
+                ref mut coordinate,
+                ref mut array_index,
+                ref mut value,
+                ..
+            } => {
+                remap_handle(image, remap);
+                remap_handle(coordinate, remap);
+                if let Some(ref mut h) = *array_index {
+                    remap_handle(h, remap);
+                }
+                remap_handle(value, remap);
+            }
+            Statement::CooperativeMatrixStore {
+                ref mut pointer,
+                ref mut value,
+                ref mut stride,
+                ..
+            } => {
+                remap_handle(pointer, remap);
+                remap_handle(value, remap);
+                remap_handle(stride, remap);
+            }
+            Statement::WorkGroupUniformLoad {
+                ref mut pointer,
+                ref mut result,
+            } => {
+                remap_handle(pointer, remap);
+                remap_handle(result, remap);
+            }
+            Statement::SubgroupBallot {
+                ref mut result,
+                ref mut predicate,
+            } => {
+                remap_handle(result, remap);
+                if let Some(ref mut h) = *predicate {
+                    remap_handle(h, remap);
+                }
+            }
+            Statement::SubgroupGather {
+                ref mut argument,
+                ref mut result,
+                ..
+            } => {
+                remap_handle(argument, remap);
+                remap_handle(result, remap);
+            }
+            Statement::SubgroupCollectiveOperation {
+                ref mut argument,
+                ref mut result,
+                ..
+            } => {
+                remap_handle(argument, remap);
+                remap_handle(result, remap);
+            }
+            Statement::Call {
+                ref mut arguments,
+                ref mut result,
+                ..
+            } => {
+                for arg in arguments {
+                    remap_handle(arg, remap);
+                }
+                if let Some(ref mut result) = *result {
+                    remap_handle(result, remap);
+                }
+            }
+            Statement::RayQuery {
+                ref mut query,
+                ref mut fun,
+            } => {
+                remap_handle(query, remap);
+                remap_ray_query_function(fun, remap);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Remap the handles nested inside a [`RayQueryFunction`], the same way
+/// [`remap_block`] does for a [`Statement::RayQuery`]'s `query` operand.
+///
+/// [`RayQueryFunction`]: crate::RayQueryFunction
+fn remap_ray_query_function(
+    fun: &mut crate::RayQueryFunction,
+    remap: &alloc::collections::BTreeMap<Handle<Expression>, Handle<Expression>>,
+) {
+    match *fun {
+        crate::RayQueryFunction::Initialize {
+            ref mut acceleration_structure,
+            ref mut descriptor,
+        } => {
+            remap_handle(acceleration_structure, remap);
+            remap_handle(descriptor, remap);
+        }
+        crate::RayQueryFunction::Proceed { ref mut result } => remap_handle(result, remap),
+        crate::RayQueryFunction::GenerateIntersection { ref mut hit_t } => {
+            remap_handle(hit_t, remap)
+        }
+        crate::RayQueryFunction::ConfirmIntersection => {}
+        #[allow(unreachable_patterns)]
+        _ => {}
+    }
+}
+
+/// Compute `range`'s new bounds from the handles `remap` kept, or `None` if
+/// every expression the range covered was eliminated.
+fn remap_emit_range(
+    range: &crate::arena::Range<Expression>,
+    remap: &alloc::collections::BTreeMap<Handle<Expression>, Handle<Expression>>,
+) -> Option<crate::arena::Range<Expression>> {
+    let bounds = range.index_range();
+    let mut first = None;
+    let mut last = None;
+    for index in bounds.start.index()..bounds.end.index() {
+        if let Some(&new_handle) = remap.get(&Handle::from_usize(index)) {
+            first.get_or_insert(new_handle);
+            last = Some(new_handle);
+        }
+    }
+    match (first, last) {
+        (Some(first), Some(last)) => Some(crate::arena::Range::new_from_bounds(first, last)),
+        _ => None,
+    }
+}
+
+fn remap_handle(
+    handle: &mut Handle<Expression>,
+    remap: &alloc::collections::BTreeMap<Handle<Expression>, Handle<Expression>>,
+) {
+    if let Some(&new) = remap.get(handle) {
+        *handle = new;
+    }
+}
+
+fn compact_global_expressions(module: &mut Module) {
+    // Constant, override and global variable inits, and entry points'
+    // workgroup size overrides, are the only roots into the global arena;
+    // everything else (folded-away override inits, etc.) is dropped the same
+    // way as in `eliminate_function`.
+    let mut reachable = alloc::collections::BTreeSet::new();
+    for (_, constant) in module.constants.iter() {
+        mark_expr(constant.init, &module.global_expressions, &mut reachable);
+    }
+    for (_, override_) in module.overrides.iter() {
+        if let Some(init) = override_.init {
+            mark_expr(init, &module.global_expressions, &mut reachable);
+        }
+    }
+    for (_, global) in module.global_variables.iter() {
+        if let Some(init) = global.init {
+            mark_expr(init, &module.global_expressions, &mut reachable);
+        }
+    }
+    for entry_point in &module.entry_points {
+        for &handle in entry_point.workgroup_size_overrides.iter().flatten().flatten() {
+            mark_expr(handle, &module.global_expressions, &mut reachable);
+        }
+    }
+    let (new_arena, remap) = compact(&module.global_expressions, &reachable);
+    module.global_expressions = new_arena;
+    for (_, constant) in module.constants.iter_mut() {
+        remap_handle(&mut constant.init, &remap);
+    }
+    for (_, override_) in module.overrides.iter_mut() {
+        if let Some(ref mut init) = override_.init {
+            remap_handle(init, &remap);
+        }
+    }
+    for (_, global) in module.global_variables.iter_mut() {
+        if let Some(ref mut init) = global.init {
+            remap_handle(init, &remap);
+        }
+    }
+    for entry_point in &mut module.entry_points {
+        for handle in entry_point.workgroup_size_overrides.iter_mut().flatten().flatten() {
+            remap_handle(handle, &remap);
+        }
+    }
+}
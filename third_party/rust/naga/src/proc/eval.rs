@@ -0,0 +1,410 @@
+/*! Scalar evaluation helpers backing [`constant_fold`](crate::proc::constant_fold).
+
+Each function here mirrors one [`Expression`] variant that
+[`constant_fold::try_fold`] can fold: given already-literal operands, compute
+the literal result, or return `None` when the operation isn't one this
+best-effort pass knows how to evaluate (an unimplemented [`MathFunction`]) or
+would be unsound to evaluate at all (overflow, divide-by-zero, NaN/Inf) —
+[`constant_fold`] leaves such expressions unfolded rather than guessing, per
+its own module doc.
+
+[`constant_fold::try_fold`]: crate::proc::constant_fold::try_fold
+*/
+
+use crate::arena::{Handle, UniqueArena};
+use crate::{
+    BinaryOperator, Bytes, Literal, MathFunction, RelationalFunction, Scalar, ScalarKind, Span,
+    Type, TypeInner, UnaryOperator, VectorSize,
+};
+
+/// Evaluate a unary operator applied to an already-folded literal.
+pub fn eval_unary(op: UnaryOperator, operand: Literal) -> Option<Literal> {
+    match (op, operand) {
+        (UnaryOperator::Negate, Literal::F64(v)) => Some(Literal::F64(-v)),
+        (UnaryOperator::Negate, Literal::F32(v)) => Some(Literal::F32(-v)),
+        (UnaryOperator::Negate, Literal::F16(v)) => Some(Literal::F16(-v)),
+        (UnaryOperator::Negate, Literal::BF16(v)) => Some(Literal::BF16(-v)),
+        (UnaryOperator::Negate, Literal::I32(v)) => v.checked_neg().map(Literal::I32),
+        (UnaryOperator::Negate, Literal::I64(v)) => v.checked_neg().map(Literal::I64),
+        (UnaryOperator::Negate, Literal::AbstractInt(v)) => v.checked_neg().map(Literal::AbstractInt),
+        (UnaryOperator::Negate, Literal::AbstractFloat(v)) => Some(Literal::AbstractFloat(-v)),
+        (UnaryOperator::LogicalNot, Literal::Bool(v)) => Some(Literal::Bool(!v)),
+        (UnaryOperator::BitwiseNot, Literal::U32(v)) => Some(Literal::U32(!v)),
+        (UnaryOperator::BitwiseNot, Literal::I32(v)) => Some(Literal::I32(!v)),
+        (UnaryOperator::BitwiseNot, Literal::U64(v)) => Some(Literal::U64(!v)),
+        (UnaryOperator::BitwiseNot, Literal::I64(v)) => Some(Literal::I64(!v)),
+        _ => None,
+    }
+}
+
+macro_rules! float_binary {
+    ($left:expr, $right:expr, $op:expr, $variant:ident) => {
+        match $op {
+            BinaryOperator::Add => Some(Literal::$variant($left + $right)),
+            BinaryOperator::Subtract => Some(Literal::$variant($left - $right)),
+            BinaryOperator::Multiply => Some(Literal::$variant($left * $right)),
+            BinaryOperator::Divide if $right != Default::default() => {
+                Some(Literal::$variant($left / $right))
+            }
+            BinaryOperator::Modulo if $right != Default::default() => {
+                Some(Literal::$variant($left % $right))
+            }
+            BinaryOperator::Equal => Some(Literal::Bool($left == $right)),
+            BinaryOperator::NotEqual => Some(Literal::Bool($left != $right)),
+            BinaryOperator::Less => Some(Literal::Bool($left < $right)),
+            BinaryOperator::LessEqual => Some(Literal::Bool($left <= $right)),
+            BinaryOperator::Greater => Some(Literal::Bool($left > $right)),
+            BinaryOperator::GreaterEqual => Some(Literal::Bool($left >= $right)),
+            _ => None,
+        }
+    };
+}
+
+macro_rules! int_binary {
+    ($left:expr, $right:expr, $op:expr, $variant:ident) => {
+        match $op {
+            BinaryOperator::Add => $left.checked_add($right).map(Literal::$variant),
+            BinaryOperator::Subtract => $left.checked_sub($right).map(Literal::$variant),
+            BinaryOperator::Multiply => $left.checked_mul($right).map(Literal::$variant),
+            BinaryOperator::Divide => $left.checked_div($right).map(Literal::$variant),
+            BinaryOperator::Modulo => $left.checked_rem($right).map(Literal::$variant),
+            BinaryOperator::Equal => Some(Literal::Bool($left == $right)),
+            BinaryOperator::NotEqual => Some(Literal::Bool($left != $right)),
+            BinaryOperator::Less => Some(Literal::Bool($left < $right)),
+            BinaryOperator::LessEqual => Some(Literal::Bool($left <= $right)),
+            BinaryOperator::Greater => Some(Literal::Bool($left > $right)),
+            BinaryOperator::GreaterEqual => Some(Literal::Bool($left >= $right)),
+            BinaryOperator::And => Some(Literal::$variant($left & $right)),
+            BinaryOperator::ExclusiveOr => Some(Literal::$variant($left ^ $right)),
+            BinaryOperator::InclusiveOr => Some(Literal::$variant($left | $right)),
+            BinaryOperator::ShiftLeft => $left
+                .checked_shl($right as u32)
+                .map(Literal::$variant),
+            BinaryOperator::ShiftRight => $left
+                .checked_shr($right as u32)
+                .map(Literal::$variant),
+            _ => None,
+        }
+    };
+}
+
+/// Evaluate a binary operator applied to two already-folded literals.
+///
+/// Both operands must already be the same [`Literal`] variant; folding never
+/// performs the implicit abstract-to-concrete conversions a type-checking
+/// pass would, so a still-mismatched pair is left unfolded.
+pub fn eval_binary(op: BinaryOperator, left: Literal, right: Literal) -> Option<Literal> {
+    match (left, right) {
+        (Literal::F64(l), Literal::F64(r)) => float_binary!(l, r, op, F64).and_then(finite),
+        (Literal::F32(l), Literal::F32(r)) => float_binary!(l, r, op, F32).and_then(finite),
+        (Literal::AbstractFloat(l), Literal::AbstractFloat(r)) => {
+            float_binary!(l, r, op, AbstractFloat).and_then(finite)
+        }
+        (Literal::U32(l), Literal::U32(r)) => int_binary!(l, r, op, U32),
+        (Literal::I32(l), Literal::I32(r)) => int_binary!(l, r, op, I32),
+        (Literal::U64(l), Literal::U64(r)) => int_binary!(l, r, op, U64),
+        (Literal::I64(l), Literal::I64(r)) => int_binary!(l, r, op, I64),
+        (Literal::AbstractInt(l), Literal::AbstractInt(r)) => int_binary!(l, r, op, AbstractInt),
+        (Literal::Bool(l), Literal::Bool(r)) => match op {
+            BinaryOperator::Equal => Some(Literal::Bool(l == r)),
+            BinaryOperator::NotEqual => Some(Literal::Bool(l != r)),
+            BinaryOperator::LogicalAnd | BinaryOperator::And => Some(Literal::Bool(l && r)),
+            BinaryOperator::LogicalOr | BinaryOperator::InclusiveOr => Some(Literal::Bool(l || r)),
+            BinaryOperator::ExclusiveOr => Some(Literal::Bool(l != r)),
+            _ => None,
+        },
+        // `F16`/`BF16` pairs are not folded here; like any mismatched pair,
+        // they are left unfolded.
+        _ => None,
+    }
+}
+
+/// `Some(literal)` unless `literal` is a NaN or infinite float, which
+/// folding leaves unfolded.
+fn finite(literal: Literal) -> Option<Literal> {
+    match literal {
+        Literal::F64(v) | Literal::AbstractFloat(v) if !v.is_finite() => None,
+        Literal::F32(v) if !v.is_finite() => None,
+        _ => Some(literal),
+    }
+}
+
+/// Evaluate an `As` cast applied to an already-folded literal.
+///
+/// `convert: None` means a bitcast (reinterpret the same bits as a different
+/// kind, same width); only the common 32-bit float/int reinterpretations are
+/// handled, matching this pass's best-effort scope. `convert: Some(width)` is
+/// a numeric conversion to the target kind and width.
+pub fn eval_as(operand: Literal, kind: ScalarKind, convert: Option<Bytes>) -> Option<Literal> {
+    let Some(width) = convert else {
+        return match (operand, kind) {
+            (Literal::F32(v), ScalarKind::Sint) => Some(Literal::I32(v.to_bits() as i32)),
+            (Literal::F32(v), ScalarKind::Uint) => Some(Literal::U32(v.to_bits())),
+            (Literal::I32(v), ScalarKind::Float) => finite(Literal::F32(f32::from_bits(v as u32))),
+            (Literal::U32(v), ScalarKind::Float) => finite(Literal::F32(f32::from_bits(v))),
+            (Literal::U32(v), ScalarKind::Sint) => Some(Literal::I32(v as i32)),
+            (Literal::I32(v), ScalarKind::Uint) => Some(Literal::U32(v as u32)),
+            _ => None,
+        };
+    };
+
+    // Converting values, not bits: go through `f64`/`i64` as a common
+    // intermediate, then narrow to the requested width.
+    // An integer target only folds when the value's whole part fits it; an
+    // out-of-range or NaN source is left unfolded instead of saturating.
+    let as_f64 = literal_as_f64(operand)?;
+    let whole = as_f64.trunc();
+    match (kind, width) {
+        (ScalarKind::Float, 4) => finite(Literal::F32(as_f64 as f32)),
+        (ScalarKind::Float, 8) => finite(Literal::F64(as_f64)),
+        (ScalarKind::Sint, 4) if (i32::MIN as f64..=i32::MAX as f64).contains(&whole) => {
+            Some(Literal::I32(as_f64 as i32))
+        }
+        (ScalarKind::Sint, 8) if (i64::MIN as f64..i64::MAX as f64).contains(&whole) => {
+            Some(Literal::I64(as_f64 as i64))
+        }
+        (ScalarKind::Uint, 4) if (0.0..=u32::MAX as f64).contains(&whole) => {
+            Some(Literal::U32(as_f64 as u32))
+        }
+        (ScalarKind::Uint, 8) if (0.0..u64::MAX as f64).contains(&whole) => {
+            Some(Literal::U64(as_f64 as u64))
+        }
+        (ScalarKind::Bool, _) => Some(Literal::Bool(as_f64 != 0.0)),
+        _ => None,
+    }
+}
+
+fn literal_as_f64(literal: Literal) -> Option<f64> {
+    match literal {
+        Literal::F64(v) => Some(v),
+        Literal::F32(v) => Some(v as f64),
+        Literal::U32(v) => Some(v as f64),
+        Literal::I32(v) => Some(v as f64),
+        Literal::U64(v) => Some(v as f64),
+        Literal::I64(v) => Some(v as f64),
+        Literal::Bool(v) => Some(v as u32 as f64),
+        Literal::AbstractInt(v) => Some(v as f64),
+        Literal::AbstractFloat(v) => Some(v),
+        Literal::F16(_) | Literal::BF16(_) => None,
+    }
+}
+
+/// Evaluate a [`MathFunction`] call over already-folded literal arguments.
+///
+/// Only a set of scalar functions that are meaningful to fold ahead of a
+/// backend is implemented: single-argument ones, plus `Min`/`Max`/`Clamp`/
+/// `Pow`. Anything else (vector-geometry functions like
+/// [`Dot`](MathFunction::Dot), or functions this pass simply hasn't been
+/// taught) returns `None` and is left for the backend, as is a NaN or
+/// infinite result.
+pub fn eval_math(fun: MathFunction, args: &[Literal]) -> Option<Literal> {
+    let arg = *args.first()?;
+    let result = match fun {
+        MathFunction::Abs => match arg {
+            Literal::F64(v) => Some(Literal::F64(v.abs())),
+            Literal::F32(v) => Some(Literal::F32(v.abs())),
+            Literal::AbstractFloat(v) => Some(Literal::AbstractFloat(v.abs())),
+            Literal::I32(v) => v.checked_abs().map(Literal::I32),
+            Literal::I64(v) => v.checked_abs().map(Literal::I64),
+            Literal::AbstractInt(v) => v.checked_abs().map(Literal::AbstractInt),
+            Literal::U32(_) | Literal::U64(_) => Some(arg),
+            _ => None,
+        },
+        MathFunction::Min | MathFunction::Max => {
+            let other = *args.get(1)?;
+            match (arg, other, fun) {
+                (Literal::F64(a), Literal::F64(b), MathFunction::Min) => {
+                    Some(Literal::F64(a.min(b)))
+                }
+                (Literal::F64(a), Literal::F64(b), MathFunction::Max) => {
+                    Some(Literal::F64(a.max(b)))
+                }
+                (Literal::F32(a), Literal::F32(b), MathFunction::Min) => {
+                    Some(Literal::F32(a.min(b)))
+                }
+                (Literal::F32(a), Literal::F32(b), MathFunction::Max) => {
+                    Some(Literal::F32(a.max(b)))
+                }
+                (Literal::I32(a), Literal::I32(b), MathFunction::Min) => {
+                    Some(Literal::I32(a.min(b)))
+                }
+                (Literal::I32(a), Literal::I32(b), MathFunction::Max) => {
+                    Some(Literal::I32(a.max(b)))
+                }
+                (Literal::U32(a), Literal::U32(b), MathFunction::Min) => {
+                    Some(Literal::U32(a.min(b)))
+                }
+                (Literal::U32(a), Literal::U32(b), MathFunction::Max) => {
+                    Some(Literal::U32(a.max(b)))
+                }
+                _ => None,
+            }
+        }
+        MathFunction::Clamp => {
+            let low = *args.get(1)?;
+            let high = *args.get(2)?;
+            match (arg, low, high) {
+                (Literal::F32(v), Literal::F32(lo), Literal::F32(hi)) => {
+                    Some(Literal::F32(v.clamp(lo, hi)))
+                }
+                (Literal::I32(v), Literal::I32(lo), Literal::I32(hi)) => {
+                    Some(Literal::I32(v.clamp(lo, hi)))
+                }
+                (Literal::U32(v), Literal::U32(lo), Literal::U32(hi)) => {
+                    Some(Literal::U32(v.clamp(lo, hi)))
+                }
+                _ => None,
+            }
+        }
+        MathFunction::Saturate => match arg {
+            Literal::F32(v) => Some(Literal::F32(v.clamp(0.0, 1.0))),
+            Literal::F64(v) => Some(Literal::F64(v.clamp(0.0, 1.0))),
+            _ => None,
+        },
+        MathFunction::Sign => match arg {
+            Literal::F32(v) => Some(Literal::F32(v.signum())),
+            Literal::F64(v) => Some(Literal::F64(v.signum())),
+            Literal::I32(v) => Some(Literal::I32(v.signum())),
+            _ => None,
+        },
+        MathFunction::Ceil => match arg {
+            Literal::F32(v) => Some(Literal::F32(v.ceil())),
+            Literal::F64(v) => Some(Literal::F64(v.ceil())),
+            _ => None,
+        },
+        MathFunction::Floor => match arg {
+            Literal::F32(v) => Some(Literal::F32(v.floor())),
+            Literal::F64(v) => Some(Literal::F64(v.floor())),
+            _ => None,
+        },
+        MathFunction::Round => match arg {
+            Literal::F32(v) => Some(Literal::F32(v.round_ties_even())),
+            Literal::F64(v) => Some(Literal::F64(v.round_ties_even())),
+            _ => None,
+        },
+        MathFunction::Trunc => match arg {
+            Literal::F32(v) => Some(Literal::F32(v.trunc())),
+            Literal::F64(v) => Some(Literal::F64(v.trunc())),
+            _ => None,
+        },
+        MathFunction::Fract => match arg {
+            Literal::F32(v) => Some(Literal::F32(v.fract())),
+            Literal::F64(v) => Some(Literal::F64(v.fract())),
+            _ => None,
+        },
+        MathFunction::Sqrt => match arg {
+            Literal::F32(v) if v >= 0.0 => Some(Literal::F32(v.sqrt())),
+            Literal::F64(v) if v >= 0.0 => Some(Literal::F64(v.sqrt())),
+            _ => None,
+        },
+        MathFunction::Pow => {
+            let exponent = *args.get(1)?;
+            match (arg, exponent) {
+                (Literal::F32(base), Literal::F32(exp)) => Some(Literal::F32(base.powf(exp))),
+                (Literal::F64(base), Literal::F64(exp)) => Some(Literal::F64(base.powf(exp))),
+                _ => None,
+            }
+        }
+        MathFunction::CountOneBits => match arg {
+            Literal::U32(v) => Some(Literal::U32(v.count_ones())),
+            Literal::I32(v) => Some(Literal::U32(v.count_ones())),
+            _ => None,
+        },
+        MathFunction::CountLeadingZeros => match arg {
+            Literal::U32(v) => Some(Literal::U32(v.leading_zeros())),
+            Literal::I32(v) => Some(Literal::U32(v.leading_zeros())),
+            _ => None,
+        },
+        MathFunction::CountTrailingZeros => match arg {
+            Literal::U32(v) => Some(Literal::U32(v.trailing_zeros())),
+            Literal::I32(v) => Some(Literal::U32(v.trailing_zeros())),
+            _ => None,
+        },
+        MathFunction::ReverseBits => match arg {
+            Literal::U32(v) => Some(Literal::U32(v.reverse_bits())),
+            Literal::I32(v) => Some(Literal::I32(v.reverse_bits())),
+            _ => None,
+        },
+        _ => None,
+    };
+    result.and_then(finite)
+}
+
+/// Evaluate a [`RelationalFunction`] call over an already-folded literal.
+///
+/// Both callers (`constant_fold`'s `literal_operand` and `override_resolve`'s
+/// `fold_to_literal`) only ever pass a *scalar* literal, never a `Compose`d
+/// vector, so [`RelationalFunction::All`]/[`Any`]
+/// reduce to the identity on that one bool; there is no vector form to
+/// actually reduce over at this stage.
+///
+/// [`Any`]: RelationalFunction::Any
+pub fn eval_relational(fun: RelationalFunction, argument: Literal) -> Option<Literal> {
+    match (fun, argument) {
+        (RelationalFunction::All | RelationalFunction::Any, Literal::Bool(v)) => {
+            Some(Literal::Bool(v))
+        }
+        (RelationalFunction::IsNan, Literal::F32(v)) => Some(Literal::Bool(v.is_nan())),
+        (RelationalFunction::IsNan, Literal::F64(v)) => Some(Literal::Bool(v.is_nan())),
+        (RelationalFunction::IsInf, Literal::F32(v)) => Some(Literal::Bool(v.is_infinite())),
+        (RelationalFunction::IsInf, Literal::F64(v)) => Some(Literal::Bool(v.is_infinite())),
+        _ => None,
+    }
+}
+
+/// The [`Scalar`] a literal's runtime variant corresponds to, for building
+/// the vector type in [`vector_type_for`].
+fn scalar_for_literal(literal: Literal) -> Option<Scalar> {
+    let (kind, width) = match literal {
+        Literal::F64(_) => (ScalarKind::Float, 8),
+        Literal::F32(_) => (ScalarKind::Float, 4),
+        Literal::F16(_) => (ScalarKind::Float, 2),
+        Literal::BF16(_) => (ScalarKind::Bfloat16, 2),
+        Literal::U32(_) => (ScalarKind::Uint, 4),
+        Literal::I32(_) => (ScalarKind::Sint, 4),
+        Literal::U64(_) => (ScalarKind::Uint, 8),
+        Literal::I64(_) => (ScalarKind::Sint, 8),
+        Literal::Bool(_) => (ScalarKind::Bool, 1),
+        // Abstract literals never reach a backend directly; a `Splat`/
+        // `Swizzle` folded this early in an all-abstract subtree has no
+        // concrete width to assign yet.
+        Literal::AbstractInt(_) | Literal::AbstractFloat(_) => return None,
+    };
+    Some(Scalar { kind, width })
+}
+
+/// The `Handle<Type>` for a vector of `size` components of whatever scalar
+/// kind/width `literal` is, inserting it into `types` if not already
+/// present.
+///
+/// Takes `types` directly (rather than being threaded in via some larger
+/// context struct) because this is the only place in [`constant_fold`] that
+/// needs to allocate a type, mirroring how [`arbitrary_valid`] builds
+/// one-off vector types straight off `module.types`.
+///
+/// [`arbitrary_valid`]: crate::arbitrary_valid
+pub fn vector_type_for(
+    types: &mut UniqueArena<Type>,
+    literal: Literal,
+    size: VectorSize,
+) -> Option<Handle<Type>> {
+    let scalar = scalar_for_literal(literal)?;
+    Some(types.insert(
+        Type {
+            name: None,
+            inner: TypeInner::Vector { size, scalar },
+        },
+        Span::UNDEFINED,
+    ))
+}
+
+/// Narrow an already-folded literal to a `u32` index, for `Access`'s dynamic
+/// index operand.
+pub fn literal_as_u32(literal: Literal) -> Option<u32> {
+    match literal {
+        Literal::U32(v) => Some(v),
+        Literal::I32(v) => u32::try_from(v).ok(),
+        Literal::AbstractInt(v) => u32::try_from(v).ok(),
+        _ => None,
+    }
+}
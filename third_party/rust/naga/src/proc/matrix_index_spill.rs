@@ -0,0 +1,223 @@
+/*! Spilling dynamically-indexed matrices to a local variable.
+
+[`Expression::Access`]'s docs require that a [`TypeInner::Matrix`] base
+indexed by a non-constant value only appear behind a [`Pointer`]; this forces
+every front end and back end to deal with the case where it doesn't. This pass
+legalizes such accesses automatically: it spills the matrix value into a
+synthesized [`LocalVariable`], stores the original value into it, and rewrites
+the access to index through the resulting pointer instead.
+
+There is no general typifier in this tree to ask "what type does this
+expression resolve to" — resolving an arbitrary expression needs the type
+arena, the enclosing module's globals, and the function's own signature, and
+nothing here builds that. [`resolve_matrix_type`] is deliberately narrower: it
+only chases the handful of expression forms that can directly produce a
+non-pointer matrix *value* — [`Compose`], a [`Load`] straight from a matrix
+[`LocalVariable`](Expression::LocalVariable) or a pointer-typed
+[`FunctionArgument`], or a by-value [`FunctionArgument`] — which covers this
+pass's own motivating case. A `Load` through any other pointer is not
+resolved.
+An `Access`/`AccessIndex` chain several levels deep into an array of matrices
+resolves to `None` and is left unspilled rather than guessed at.
+
+[`Compose`]: Expression::Compose
+[`Load`]: Expression::Load
+[`FunctionArgument`]: Expression::FunctionArgument
+*/
+
+use crate::arena::{Handle, UniqueArena};
+use crate::{Block, Expression, Function, LocalVariable, Statement, Type, TypeInner};
+
+/// Legalize every non-pointer, dynamically-indexed matrix `Access` in
+/// `function`, given `types` (typically `module.types`) to resolve operand
+/// types against.
+pub fn legalize_function(function: &mut Function, types: &UniqueArena<Type>) {
+    let mut spills = Vec::new();
+    collect_spills(&function.body, function, types, &mut spills);
+    // Every nested block repeats `collect_spills`' arena scan, so drop the
+    // duplicates that produces before applying anything twice.
+    spills.sort_by_key(|spill| spill.access_expr);
+    spills.dedup_by_key(|spill| spill.access_expr);
+    for spill in spills.into_iter().rev() {
+        apply_spill(function, types, spill);
+    }
+}
+
+/// Resolve `expr`'s type, to the extent this pass's narrow, local type
+/// resolution can — see the module doc for exactly which forms are handled.
+fn resolve_matrix_type(
+    function: &Function,
+    types: &UniqueArena<Type>,
+    expr: Handle<Expression>,
+) -> Option<Handle<Type>> {
+    let ty = match function.expressions[expr] {
+        Expression::Compose { ty, .. } => ty,
+        // A `LocalVariable` expression stores its own pointee type
+        // directly; a `FunctionArgument` passed by pointer instead has an
+        // actual `Pointer` type registered in `types` to unwrap.
+        Expression::Load { pointer } => match function.expressions[pointer] {
+            Expression::LocalVariable(local) => function.local_variables[local].ty,
+            Expression::FunctionArgument(index) => {
+                let arg = function.arguments.get(index as usize)?;
+                match types[arg.ty].inner {
+                    TypeInner::Pointer { base, .. } => base,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        },
+        Expression::FunctionArgument(index) => function.arguments.get(index as usize)?.ty,
+        _ => return None,
+    };
+    matches!(types[ty].inner, TypeInner::Matrix { .. }).then_some(ty)
+}
+
+struct Spill {
+    access_expr: Handle<Expression>,
+    base_expr: Handle<Expression>,
+    index_expr: Handle<Expression>,
+}
+
+fn collect_spills(
+    block: &Block,
+    function: &Function,
+    types: &UniqueArena<Type>,
+    out: &mut Vec<Spill>,
+) {
+    for (handle, expr) in function.expressions.iter() {
+        if let Expression::Access { base, index } = *expr {
+            // `resolve_matrix_type` only ever resolves a non-pointer matrix
+            // *value* (see the module doc); a pointer-typed `base` — the
+            // already-legal case — simply doesn't match any of its arms and
+            // falls out as `None`, so there's no separate pointer check
+            // needed here.
+            let is_matrix_value = resolve_matrix_type(function, types, base).is_some();
+            if is_matrix_value && is_dynamic_index(&function.expressions, index) {
+                out.push(Spill {
+                    access_expr: handle,
+                    base_expr: base,
+                    index_expr: index,
+                });
+            }
+        }
+    }
+    // The arena scan above already sees every expression, including those
+    // emitted inside branches; each nested block visited here repeats it.
+    for statement in block.iter() {
+        match *statement {
+            Statement::Block(ref inner) => collect_spills(inner, function, types, out),
+            Statement::If {
+                ref accept,
+                ref reject,
+                ..
+            } => {
+                collect_spills(accept, function, types, out);
+                collect_spills(reject, function, types, out);
+            }
+            Statement::Loop {
+                ref body,
+                ref continuing,
+                ..
+            } => {
+                collect_spills(body, function, types, out);
+                collect_spills(continuing, function, types, out);
+            }
+            Statement::Switch { ref cases, .. } => {
+                for case in cases {
+                    collect_spills(&case.body, function, types, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_dynamic_index(expressions: &crate::Arena<Expression>, index: Handle<Expression>) -> bool {
+    !matches!(expressions[index], Expression::Literal(_))
+}
+
+/// Introduce a `LocalVariable` holding `spill.base_expr`'s matrix value,
+/// `Store` the original value into it ahead of the `Emit` range that
+/// produces the access, and rewrite the access to go through the new
+/// pointer.
+///
+/// `spill.access_expr`'s handle must keep resolving to the *value*, since
+/// every other expression that already referenced it expects a value, not a
+/// pointer: the new pointer-typed `Access` is appended under a fresh handle,
+/// and `spill.access_expr` is overwritten in place with a `Load` of it.
+fn apply_spill(function: &mut Function, types: &UniqueArena<Type>, spill: Spill) {
+    let Some(ty) = resolve_matrix_type(function, types, spill.base_expr) else {
+        return;
+    };
+    let local = function.local_variables.append(
+        LocalVariable {
+            name: None,
+            ty,
+            init: None,
+        },
+        crate::Span::UNDEFINED,
+    );
+    let local_expr = function
+        .expressions
+        .append(Expression::LocalVariable(local), crate::Span::UNDEFINED);
+
+    insert_store_before(&mut function.body, spill.access_expr, local_expr, spill.base_expr);
+
+    let pointer_expr_start = function.expressions.len() as u32;
+    let pointer_expr = function.expressions.append(
+        Expression::Access {
+            base: local_expr,
+            index: spill.index_expr,
+        },
+        crate::Span::UNDEFINED,
+    );
+    let pointer_emit_range = function.expressions.range_from(pointer_expr_start);
+    insert_emit_before(&mut function.body, spill.access_expr, pointer_emit_range);
+
+    function.expressions[spill.access_expr] = Expression::Load {
+        pointer: pointer_expr,
+    };
+}
+
+/// Walk `block` to find the statement whose `Emit` range covers
+/// `access_expr`, and insert a `Store` of `value` into `pointer` immediately
+/// before it.
+fn insert_store_before(
+    block: &mut Block,
+    access_expr: Handle<Expression>,
+    pointer: Handle<Expression>,
+    value: Handle<Expression>,
+) {
+    for i in 0..block.len() {
+        if let Statement::Emit(ref range) = block[i] {
+            if range.index_range().contains(&access_expr) {
+                block.insert(
+                    i,
+                    Statement::Store {
+                        pointer,
+                        value,
+                    },
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Walk `block` to find the statement whose `Emit` range covers
+/// `access_expr`, and insert an `Emit` of `range` immediately before it, so
+/// the expressions `range` covers are evaluated before that statement runs.
+fn insert_emit_before(
+    block: &mut Block,
+    access_expr: Handle<Expression>,
+    range: crate::arena::Range<Expression>,
+) {
+    for i in 0..block.len() {
+        if let Statement::Emit(ref existing) = block[i] {
+            if existing.index_range().contains(&access_expr) {
+                block.insert(i, Statement::Emit(range));
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+/*! Helpers for ray queries against procedural (AABB) geometry.
+
+The IR already let a front end express most of procedural-candidate support
+— [`RayQueryIntersection::Aabb`] is the candidate kind,
+[`Expression::RayQueryGetIntersection`] (with `committed: false`) reads back
+the current candidate (including its kind) when the traversal hits an
+axis-aligned bounding box rather than a triangle, and
+[`RayQueryFunction::GenerateIntersection`]/[`ConfirmIntersection`] already let
+the shader commit a procedural hit with an app-computed `t` or confirm a
+triangle hit — except for the two pieces of per-candidate data
+`RayQueryGetIntersection`'s fixed struct shape has no room for: a candidate's
+AABB bounds, and its source instance's custom index/ID. Vulkan exposes both
+as separate `rayQueryGetIntersectionCandidateAABB*`/
+`rayQueryGetIntersectionInstance*EXT` queries; this module adds the matching
+IR: [`Expression::RayQueryGetCandidateBounds`]/[`RayQueryGetCandidateInstance`],
+plus [`candidate_bounds_type`]/[`candidate_instance_type`] to build the small
+struct types they resolve to.
+
+This is still IR-only: no backend in this tree lowers these two expressions
+(there's no SPIR-V backend module here at all, `src/back/` has no `spv.rs`),
+but that mirrors every other `RayQueryFunction`/`RayQueryGetIntersection`
+variant already in the IR, none of which has a backend to lower into here
+either.
+
+[`Expression::RayQueryGetIntersection`]: crate::Expression::RayQueryGetIntersection
+[`RayQueryFunction::GenerateIntersection`]: crate::RayQueryFunction::GenerateIntersection
+[`ConfirmIntersection`]: crate::RayQueryFunction::ConfirmIntersection
+[`Expression::RayQueryGetCandidateBounds`]: crate::Expression::RayQueryGetCandidateBounds
+[`RayQueryGetCandidateInstance`]: crate::Expression::RayQueryGetCandidateInstance
+*/
+
+use crate::arena::{Handle, UniqueArena};
+use crate::{RayFlag, Scalar, ScalarKind, StructMember, Type, TypeInner, VectorSize};
+
+/// Whether a ray query issued with `flags` may ever report a
+/// [`RayQueryIntersection::Aabb`] candidate, and so may legally call
+/// [`RayQueryFunction::GenerateIntersection`].
+///
+/// [`RayQueryIntersection::Aabb`]: crate::RayQueryIntersection::Aabb
+/// [`RayQueryFunction::GenerateIntersection`]: crate::RayQueryFunction::GenerateIntersection
+pub fn allows_procedural_candidates(flags: RayFlag) -> bool {
+    !flags.contains(RayFlag::SKIP_AABBS)
+}
+
+/// Get (inserting if necessary) the `Handle<Type>` for
+/// [`SpecialTypes::ray_candidate_bounds`]: a struct of two `vec3<f32>`
+/// fields, `min` and `max`.
+///
+/// [`SpecialTypes::ray_candidate_bounds`]: crate::SpecialTypes::ray_candidate_bounds
+pub fn candidate_bounds_type(types: &mut UniqueArena<Type>) -> Handle<Type> {
+    let vec3f = types.insert(
+        Type {
+            name: None,
+            inner: TypeInner::Vector {
+                size: VectorSize::Tri,
+                scalar: Scalar {
+                    kind: ScalarKind::Float,
+                    width: 4,
+                },
+            },
+        },
+        crate::Span::UNDEFINED,
+    );
+    let vec3_size = 12;
+    types.insert(
+        Type {
+            name: Some("RayCandidateBounds".into()),
+            inner: TypeInner::Struct {
+                members: alloc::vec![
+                    StructMember {
+                        name: Some("min".into()),
+                        ty: vec3f,
+                        binding: None,
+                        offset: 0,
+                    },
+                    StructMember {
+                        name: Some("max".into()),
+                        ty: vec3f,
+                        binding: None,
+                        offset: vec3_size,
+                    },
+                ],
+                span: vec3_size * 2,
+            },
+        },
+        crate::Span::UNDEFINED,
+    )
+}
+
+/// Get (inserting if necessary) the `Handle<Type>` for
+/// [`SpecialTypes::ray_candidate_instance`]: a struct of two `u32` fields,
+/// `custom_index` and `instance_id`.
+///
+/// [`SpecialTypes::ray_candidate_instance`]: crate::SpecialTypes::ray_candidate_instance
+pub fn candidate_instance_type(types: &mut UniqueArena<Type>) -> Handle<Type> {
+    let u32_ty = types.insert(
+        Type {
+            name: None,
+            inner: TypeInner::Scalar(Scalar {
+                kind: ScalarKind::Uint,
+                width: 4,
+            }),
+        },
+        crate::Span::UNDEFINED,
+    );
+    types.insert(
+        Type {
+            name: Some("RayCandidateInstance".into()),
+            inner: TypeInner::Struct {
+                members: alloc::vec![
+                    StructMember {
+                        name: Some("custom_index".into()),
+                        ty: u32_ty,
+                        binding: None,
+                        offset: 0,
+                    },
+                    StructMember {
+                        name: Some("instance_id".into()),
+                        ty: u32_ty,
+                        binding: None,
+                        offset: 4,
+                    },
+                ],
+                span: 8,
+            },
+        },
+        crate::Span::UNDEFINED,
+    )
+}
@@ -0,0 +1,89 @@
+/*! Lowering `Switch` fallthrough into duplicated bodies.
+
+FXC/HLSL and WGSL can't express fallthrough between `switch` cases, so every
+backend previously had to reinvent this duplication itself. This pass
+rewrites any [`Statement::Switch`] containing cases with
+[`SwitchCase::fall_through`] set into an equivalent `Switch` in which only
+empty-bodied cases keep `fall_through` set (naga's encoding of multi-value
+selection, `case 1: case 2: { ... }`); every case with a body has it cleared.
+*/
+
+use crate::{Block, Statement, SwitchCase};
+
+/// Recursively lower every `Switch`'s fallthrough cases within `block`.
+pub fn lower_block(block: &mut Block) {
+    for statement in block.iter_mut() {
+        match *statement {
+            Statement::Switch { ref mut cases, .. } => lower_switch(cases),
+            Statement::Block(ref mut inner) => lower_block(inner),
+            Statement::If {
+                ref mut accept,
+                ref mut reject,
+                ..
+            } => {
+                lower_block(accept);
+                lower_block(reject);
+            }
+            Statement::Loop {
+                ref mut body,
+                ref mut continuing,
+                ..
+            } => {
+                lower_block(body);
+                lower_block(continuing);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn lower_switch(cases: &mut Vec<SwitchCase>) {
+    // Recurse into bodies first so nested switches are already fallthrough-free.
+    for case in cases.iter_mut() {
+        lower_block(&mut case.body);
+    }
+
+    let mut result = Vec::with_capacity(cases.len());
+    let mut i = 0;
+    while i < cases.len() {
+        let mut case = cases[i].clone();
+        if case.fall_through {
+            if case.body.is_empty() {
+                // The `case 1: case 2: case 3: { ... }` idiom: a run of
+                // empty-bodied fallthrough cases feeding one real body.
+                // Collapsing them to multi-value selection (rather than
+                // duplicating an empty body) requires no extra work here:
+                // each empty case is simply emitted as-is, and the following
+                // case is appended on the next loop iteration. Its
+                // `fall_through` has to stay `true` — this case still falls
+                // into the next one; only a non-empty case, which receives
+                // copies of the bodies it falls into below, has its flag
+                // cleared.
+                result.push(case);
+                i += 1;
+                continue;
+            }
+            // Append copies of the following cases' bodies, stopping after
+            // the first non-fallthrough case (inclusive). A malformed switch
+            // can have its *last* case marked `fall_through` with no case
+            // after it to fall into (no validator runs in this tree to rule
+            // that IR shape out), so bail before indexing past the end
+            // instead of panicking.
+            let mut j = i + 1;
+            while j < cases.len() {
+                let next = &cases[j];
+                case.body.extend_block(next.body.clone());
+                let stop = !next.fall_through;
+                j += 1;
+                if stop {
+                    break;
+                }
+            }
+            case.fall_through = false;
+        }
+        result.push(case);
+        i += 1;
+    }
+
+    *cases = result;
+}
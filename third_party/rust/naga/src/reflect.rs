@@ -0,0 +1,219 @@
+/*! Serializable reflection over a [`Module`]'s entry points.
+
+Consumers embedding Naga (a WGPU implementation, a shader-authoring tool)
+routinely need to know an entry point's interface — its workgroup size, its
+input/output varyings, and which bindings its body actually touches — without
+re-deriving it by walking the IR themselves. [`Module::reflect`] packages that
+up, walking each entry point's own function body to find every
+[`GlobalVariable`](crate::GlobalVariable) it reaches and whether that reach is
+ever a write.
+
+This is deliberately *not* carried as a field on [`Module`] itself: it's a
+derived view, recomputable at any time from the module, so keeping it separate
+avoids a second source of truth that could go stale as a module is
+transformed by later passes.
+
+There is no validator in this tree to ask for this analysis instead (the
+`valid` module holds only `Capabilities`), so [`reflect_globals`] does its own narrow walk:
+resolving a pointer expression down to the root [`GlobalVariable`] it reads
+through, handling only the `Access`/`AccessIndex` chains a validated module can
+actually produce a global's address out of.
+*/
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::arena::Handle;
+use crate::{AddressSpace, Binding, Expression, GlobalVariable, Module, ResourceBinding, ShaderStage, Statement};
+
+/// The reflected interface of every entry point in a [`Module`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct ModuleReflection {
+    pub entry_points: Vec<EntryPointReflection>,
+}
+
+/// The reflected interface of a single [`EntryPoint`](crate::EntryPoint).
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct EntryPointReflection {
+    pub name: String,
+    pub stage: ShaderStage,
+    /// Workgroup size with any pipeline overrides already folded; `None` if
+    /// an override involved was left unresolved (reflection was requested
+    /// before [`process_overrides`](crate::proc::override_resolve::process_overrides) ran).
+    pub workgroup_size: Option<[u32; 3]>,
+    pub inputs: Vec<VaryingReflection>,
+    pub outputs: Vec<VaryingReflection>,
+    pub globals: Vec<GlobalReflection>,
+}
+
+/// One varying (an entry point argument or result that carries a binding
+/// itself) with its binding. The members of a struct argument or result are
+/// not reflected.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct VaryingReflection {
+    pub name: Option<String>,
+    pub binding: Binding,
+}
+
+/// A [`GlobalVariable`] an entry point's body actually reads or writes, per
+/// [`reflect_globals`]'s own walk of the function.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct GlobalReflection {
+    pub name: Option<String>,
+    pub space: AddressSpace,
+    pub binding: Option<ResourceBinding>,
+    pub mutated: bool,
+}
+
+impl Module {
+    /// Reflect the interface of every entry point in this module.
+    pub fn reflect(&self) -> ModuleReflection {
+        let entry_points = self
+            .entry_points
+            .iter()
+            .map(|entry_point| {
+                let workgroup_size = if entry_point.workgroup_size_overrides.is_none() {
+                    Some(entry_point.workgroup_size)
+                } else {
+                    None
+                };
+                let uses = reflect_global_uses(&entry_point.function.body, &entry_point.function.expressions);
+                EntryPointReflection {
+                    name: entry_point.name.clone(),
+                    stage: entry_point.stage,
+                    workgroup_size,
+                    inputs: reflect_varyings(&entry_point.function.arguments),
+                    outputs: entry_point
+                        .function
+                        .result
+                        .as_ref()
+                        .map(|result| reflect_result(result))
+                        .unwrap_or_default(),
+                    globals: self.reflect_globals(&uses),
+                }
+            })
+            .collect();
+        ModuleReflection { entry_points }
+    }
+
+    fn reflect_globals(&self, uses: &BTreeMap<Handle<GlobalVariable>, bool>) -> Vec<GlobalReflection> {
+        self.global_variables
+            .iter()
+            .filter_map(|(handle, global)| {
+                uses.get(&handle).map(|&mutated| GlobalReflection {
+                    name: global.name.clone(),
+                    space: global.space,
+                    binding: global.binding.clone(),
+                    mutated,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Walk `block` to find every [`GlobalVariable`] the function reaches,
+/// mapped to whether that reach is ever a write.
+///
+/// A global reached only through a [`Statement::Store`]'s (or similar) write
+/// target is `true`; one only ever read is `false`. A global reached both
+/// ways keeps `true`, since a later read never downgrades an earlier write.
+fn reflect_global_uses(
+    block: &crate::Block,
+    expressions: &crate::Arena<Expression>,
+) -> BTreeMap<Handle<GlobalVariable>, bool> {
+    let mut uses = BTreeMap::new();
+    for (_, expr) in expressions.iter() {
+        if let Expression::GlobalVariable(handle) = *expr {
+            uses.entry(handle).or_insert(false);
+        }
+    }
+    mark_global_writes(block, expressions, &mut uses);
+    uses
+}
+
+/// Mark every global reachable through a write target in `block` as mutated.
+fn mark_global_writes(
+    block: &crate::Block,
+    expressions: &crate::Arena<Expression>,
+    uses: &mut BTreeMap<Handle<GlobalVariable>, bool>,
+) {
+    for statement in block.iter() {
+        match *statement {
+            Statement::Store { pointer, .. } => mark_write_target(expressions, pointer, uses),
+            Statement::ImageStore { image, .. } => mark_write_target(expressions, image, uses),
+            Statement::ImageAtomic { image, .. } => mark_write_target(expressions, image, uses),
+            Statement::Atomic { pointer, .. } => mark_write_target(expressions, pointer, uses),
+            Statement::Block(ref inner) => mark_global_writes(inner, expressions, uses),
+            Statement::If {
+                ref accept,
+                ref reject,
+                ..
+            } => {
+                mark_global_writes(accept, expressions, uses);
+                mark_global_writes(reject, expressions, uses);
+            }
+            Statement::Switch { ref cases, .. } => {
+                for case in cases {
+                    mark_global_writes(&case.body, expressions, uses);
+                }
+            }
+            Statement::Loop {
+                ref body,
+                ref continuing,
+                ..
+            } => {
+                mark_global_writes(body, expressions, uses);
+                mark_global_writes(continuing, expressions, uses);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Resolve `expr` down to the root [`GlobalVariable`] it addresses, if any —
+/// following the `Access`/`AccessIndex` chains a validated module can
+/// actually produce a global's address out of — and mark it mutated.
+fn mark_write_target(
+    expressions: &crate::Arena<Expression>,
+    expr: Handle<Expression>,
+    uses: &mut BTreeMap<Handle<GlobalVariable>, bool>,
+) {
+    match expressions[expr] {
+        Expression::GlobalVariable(handle) => {
+            uses.insert(handle, true);
+        }
+        Expression::Access { base, .. } => mark_write_target(expressions, base, uses),
+        Expression::AccessIndex { base, .. } => mark_write_target(expressions, base, uses),
+        _ => {}
+    }
+}
+
+fn reflect_varyings(arguments: &[crate::FunctionArgument]) -> Vec<VaryingReflection> {
+    arguments
+        .iter()
+        .filter_map(|argument| {
+            argument.binding.clone().map(|binding| VaryingReflection {
+                name: argument.name.clone(),
+                binding,
+            })
+        })
+        .collect()
+}
+
+fn reflect_result(result: &crate::FunctionResult) -> Vec<VaryingReflection> {
+    result
+        .binding
+        .clone()
+        .map(|binding| {
+            alloc::vec![VaryingReflection {
+                name: None,
+                binding,
+            }]
+        })
+        .unwrap_or_default()
+}
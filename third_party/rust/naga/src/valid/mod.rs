@@ -0,0 +1,39 @@
+/*! Capability gating for optional IR features.
+
+Several `ir` variants are only legal when the target actually supports the
+hardware feature they lower to — an atomic op on a format the device doesn't
+support, a subgroup operation restricted to a 4-lane quad. [`Capabilities`]
+names those features so a module can declare which it relies on (see
+[`GatherMode::required_capabilities`]) and a consumer can check that against
+whatever the target actually exposes before accepting the module.
+
+This is deliberately narrow: just the bitflags several `ir` doc comments
+already forward-referenced (`crate::valid::Capabilities::SHADER_INT64_ATOMIC_MIN_MAX`
+and friends), plus [`SUBGROUP_QUAD`](Capabilities::SUBGROUP_QUAD) for
+[`GatherMode::QuadBroadcast`]/[`QuadSwap`]. The full validator those doc
+comments also reference (`Validator`, `ModuleInfo`, `TypeFlags`) doesn't exist
+in this tree yet; standing those up is out of scope here.
+
+[`GatherMode::QuadBroadcast`]: crate::GatherMode::QuadBroadcast
+[`QuadSwap`]: crate::GatherMode::QuadSwap
+*/
+
+bitflags::bitflags! {
+    /// Optional hardware features an IR module may rely on.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    pub struct Capabilities: u32 {
+        /// `min`/`max` atomics on a 64-bit integer.
+        const SHADER_INT64_ATOMIC_MIN_MAX = 0x1;
+        /// The full complement of atomic ops on a 64-bit integer.
+        const SHADER_INT64_ATOMIC_ALL_OPS = 0x2;
+        /// Atomic ops on a 32-bit float.
+        const SHADER_FLOAT32_ATOMIC = 0x4;
+        /// Quad-scoped subgroup operations ([`GatherMode::QuadBroadcast`],
+        /// [`QuadSwap`]), which only gather across the 4 invocations of a
+        /// quad rather than an arbitrary subgroup.
+        ///
+        /// [`GatherMode::QuadBroadcast`]: crate::GatherMode::QuadBroadcast
+        /// [`QuadSwap`]: crate::GatherMode::QuadSwap
+        const SUBGROUP_QUAD = 0x8;
+    }
+}
@@ -2,10 +2,24 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder};
 
 use crate::{LabeledTimingSample, Suggestion, SuggestionProvider, SuggestionProviderConstraints};
 
+/// Maximum number of edits a keyword term may be from a title word and still
+/// count as a typo-tolerant match. Kept small: a larger bound starts
+/// matching titles that share little more than length with the keyword.
+const MAX_TYPO_DISTANCE: u8 = 2;
+
+/// Maps a single user-typed term (lowercase) to the alternate multi-word
+/// phrasings it should also match, e.g. `"nyc" -> ["new york city"]` so a
+/// query for "nyc pizza" can match a title indexed as "new york city pizza".
+/// Loaded alongside provider data; absent or empty entries mean no
+/// expansion.
+pub type SynonymTable = HashMap<String, Vec<String>>;
+
 /// A query for suggestions to show in the address bar.
 #[derive(Clone, Debug, Default, uniffi::Record)]
 pub struct SuggestionQuery {
@@ -15,6 +29,31 @@ pub struct SuggestionQuery {
     pub provider_constraints: Option<SuggestionProviderConstraints>,
     #[uniffi(default = None)]
     pub limit: Option<i32>,
+    #[uniffi(default = None)]
+    pub matching_strategy: Option<TermsMatchingStrategy>,
+}
+
+/// How many of a query's keyword terms a suggestion's title must match.
+///
+/// A keyword with more terms than the user actually meant to type as part of
+/// the suggestion (a trailing word not yet finished, an extra word thrown in)
+/// otherwise returns zero results even though an earlier, shorter prefix of
+/// the same keyword matched plenty.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, uniffi::Enum)]
+pub enum TermsMatchingStrategy {
+    /// Every term must match. The strictest and default strategy.
+    #[default]
+    All,
+    /// If matching every term returns nothing, retry with the last term
+    /// dropped, then the next-to-last, and so on, stopping at the first
+    /// query that returns something.
+    Last,
+    /// Like [`Self::Last`], but drops terms in order from least to most
+    /// distinctive rather than strictly from the end. Term frequency data
+    /// isn't available here, so "distinctive" is approximated by term
+    /// length: short terms (articles, common adjectives) are more likely to
+    /// be filler than a query's longest word.
+    Frequency,
 }
 
 #[derive(uniffi::Record)]
@@ -130,86 +169,568 @@ impl SuggestionQuery {
         }
     }
 
+    pub fn matching_strategy(self, matching_strategy: TermsMatchingStrategy) -> Self {
+        Self {
+            matching_strategy: Some(matching_strategy),
+            ..self
+        }
+    }
+
     /// Create an FTS query term for our keyword(s)
     pub(crate) fn fts_query(&self) -> FtsQuery<'_> {
         FtsQuery::new(&self.keyword)
     }
+
+    /// Like [`Self::fts_query`], but expands any keyword term found in
+    /// `synonyms` into an FTS `OR` group of phrase alternatives.
+    pub(crate) fn fts_query_with_synonyms<'a>(&'a self, synonyms: &'a SynonymTable) -> FtsQuery<'a> {
+        FtsQuery::new_with_synonyms(&self.keyword, synonyms)
+    }
+}
+
+/// One FTS5 match-argument term: a single word (`"word"`), a double-quoted
+/// multi-word phrase (`"word1 word2"`, matched as an exact, adjacent
+/// sequence rather than an unordered AND of its words), or a `-`-prefixed
+/// word a matching title must *not* contain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum FtsTerm<'a> {
+    Word(&'a str),
+    Phrase(Vec<&'a str>),
+    Negative(&'a str),
+}
+
+impl FtsTerm<'_> {
+    /// A non-quoted token outside a phrase: `-foo` excludes `foo`, anything
+    /// else is an ordinary word.
+    fn from_token(token: &str) -> FtsTerm<'_> {
+        match token.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => FtsTerm::Negative(rest),
+            _ => FtsTerm::Word(token),
+        }
+    }
 }
 
 pub struct FtsQuery<'a> {
     pub match_arg: String,
     pub match_arg_without_prefix_match: String,
     pub is_prefix_query: bool,
+    // Used for the approximate, best-effort matching this struct also
+    // supports (stemming detection, typo distance, highlight bounds). These
+    // flatten a quoted phrase to its individual words and drop excluded
+    // (`Negative`) words entirely, since none of them need FTS5-exact
+    // semantics and a title is never expected to contain an excluded word.
     keyword_terms: Vec<&'a str>,
+    // Parallel to `keyword_terms`: the synonym alternatives (if any) found
+    // for that term, so `match_required_stemming` can treat the term as
+    // matched when an alternative phrase appears instead of the term itself.
+    keyword_synonyms: Vec<Vec<String>>,
+    keyword_ends_in_whitespace: bool,
+    // The parsed terms, kept around so `match_arg_candidates` can rebuild a
+    // term-dropped fallback the same `FtsTerm`-aware way `match_arg` itself
+    // was built, instead of reconstructing a match argument from
+    // `keyword_terms`'s flattened, negative-stripped, phrase-unwrapped words.
+    fts_terms: Vec<FtsTerm<'a>>,
+    // Each positive (non-`Negative`) term's quoted form, precomputed at
+    // construction time since quoting a synonym-expanded `Word` needs the
+    // `SynonymTable`, which isn't kept alive past `new_with_synonyms`.
+    // Parallel to the positive subsequence of `fts_terms`. Stored as
+    // (without prefix match, with prefix match) so a fallback candidate can
+    // pick whichever applies to *its* last surviving term.
+    positive_quoted: Vec<(String, String)>,
+    // Parallel to `positive_quoted`: each term's character length, for the
+    // same "is this long enough for prefix match" / shortest-first-drop
+    // decisions `match_arg`'s own construction makes.
+    positive_lens: Vec<usize>,
+    // Every `Negative` term's quoted form (e.g. `"nike"`), precomputed for
+    // the same reason as `positive_quoted`. A fallback candidate always
+    // re-appends every one of these as a `NOT` exclusion.
+    negative_quoted: Vec<String>,
 }
 
 impl<'a> FtsQuery<'a> {
     fn new(keyword: &'a str) -> Self {
-        // Parse the `keyword` field into a set of keywords.
-        //
-        // This is used when passing the keywords into an FTS search.  It:
-        //   - Strips out any `():^*"` chars.  These are typically used for advanced searches, which
-        //     we don't support and it would be weird to only support for FTS searches.
-        //   - splits on whitespace to get a list of individual keywords
-        let keywords = Self::split_terms(keyword);
+        Self::new_with_synonyms(keyword, &SynonymTable::new())
+    }
+
+    // Parse the `keyword` field into a set of FTS terms.
+    //
+    // This is used when passing the keywords into an FTS search.  It:
+    //   - Strips out any `():^*` chars. These are typically used for advanced searches, which
+    //     we don't support and it would be weird to only support for FTS searches.
+    //   - Treats a balanced pair of `"` as an exact phrase rather than stripping them, so
+    //     `"new balance" shoes` searches for the words "new" and "balance" right next to each
+    //     other, not just somewhere in the title along with "shoes".
+    //   - Treats a `-`-prefixed word outside a phrase as excluding that word, so
+    //     `shoes -running` only matches titles that don't mention "running".
+    //   - splits on whitespace to get a list of individual keywords
+    //
+    // `synonyms` additionally expands any [`FtsTerm::Word`] it has an entry
+    // for into an FTS `OR` group of itself and each alternative phrasing,
+    // e.g. `"nyc"` becomes `("nyc" OR "new york city")`. Each alternative is
+    // quoted as an adjacency phrase rather than loose words, mirroring the
+    // MeiliSearch technique of treating multi-word synonyms as phrases to
+    // avoid blowing past the FTS5 term limit and keep relevance tight.
+    fn new_with_synonyms(keyword: &'a str, synonyms: &SynonymTable) -> Self {
+        let fts_terms = Self::parse_fts_terms(keyword);
+        let (keywords, keyword_synonyms) = Self::flatten_positive_words(&fts_terms, synonyms);
+        let keyword_ends_in_whitespace = keyword.ends_with(' ');
+
+        let negative_terms: Vec<_> = fts_terms
+            .iter()
+            .filter(|term| matches!(term, FtsTerm::Negative(_)))
+            .collect();
+        let positive_terms: Vec<_> = fts_terms
+            .iter()
+            .filter(|term| !matches!(term, FtsTerm::Negative(_)))
+            .collect();
+        // Precomputed once, up front, since [`Self::match_arg_candidates`]
+        // needs to rebuild a term-dropped match argument without a
+        // `SynonymTable` on hand (it isn't kept alive past this
+        // constructor), and without discarding phrase adjacency or negative
+        // exclusions the way reconstructing from `keywords` would.
+        let positive_quoted: Vec<(String, String)> = positive_terms
+            .iter()
+            .map(|term| {
+                (
+                    Self::quoted_term(term, false, synonyms),
+                    Self::quoted_term(term, true, synonyms),
+                )
+            })
+            .collect();
+        let positive_lens: Vec<usize> = positive_terms
+            .iter()
+            .map(|term| Self::term_char_len(term))
+            .collect();
+        let negative_quoted: Vec<String> = negative_terms
+            .iter()
+            .map(|term| Self::quoted_term(term, false, synonyms))
+            .collect();
+
         if keywords.is_empty() {
             return Self {
                 keyword_terms: keywords,
+                keyword_synonyms,
                 match_arg: String::from(r#""""#),
                 match_arg_without_prefix_match: String::from(r#""""#),
                 is_prefix_query: false,
+                keyword_ends_in_whitespace,
+                fts_terms,
+                positive_quoted,
+                positive_lens,
+                negative_quoted,
             };
         }
-        // Quote each term from `query` and join them together
-        let mut sqlite_match = keywords
+        // The prefix `*` only ever applies to the last user-typed positive
+        // term, never to a term injected by synonym expansion.
+        let last_positive_index = fts_terms
             .iter()
-            .map(|keyword| format!(r#""{keyword}""#))
-            .collect::<Vec<_>>()
-            .join(" ");
+            .rposition(|term| !matches!(term, FtsTerm::Negative(_)));
+
         // If the input is > 3 characters, and there's no whitespace at the end.
         // We want to append a `*` char to the end to do a prefix match on it.
         let total_chars = keywords.iter().fold(0, |count, s| count + s.len());
-        let query_ends_in_whitespace = keyword.ends_with(' ');
-        let prefix_match = (total_chars > 3) && !query_ends_in_whitespace;
-        let sqlite_match_without_prefix_match = sqlite_match.clone();
-        if prefix_match {
-            sqlite_match.push('*');
-        }
+        let prefix_match = (total_chars > 3) && !keyword_ends_in_whitespace;
+
+        let build_positive_arg = |apply_prefix: bool| {
+            fts_terms
+                .iter()
+                .enumerate()
+                .filter(|(_, term)| !matches!(term, FtsTerm::Negative(_)))
+                .map(|(i, term)| {
+                    let apply_prefix = apply_prefix && Some(i) == last_positive_index;
+                    Self::quoted_term(term, apply_prefix, synonyms)
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
         Self {
             keyword_terms: keywords,
+            keyword_synonyms,
             is_prefix_query: prefix_match,
-            match_arg: sqlite_match,
-            match_arg_without_prefix_match: sqlite_match_without_prefix_match,
+            match_arg: Self::append_exclusions(
+                build_positive_arg(prefix_match),
+                &negative_terms,
+                synonyms,
+            ),
+            match_arg_without_prefix_match: Self::append_exclusions(
+                build_positive_arg(false),
+                &negative_terms,
+                synonyms,
+            ),
+            keyword_ends_in_whitespace,
+            fts_terms,
+            positive_quoted,
+            positive_lens,
+            negative_quoted,
         }
     }
 
+    /// A term's total character count across its underlying word(s) — a
+    /// `Phrase`'s words summed, otherwise the single word's length. Used for
+    /// the same "is this long enough to prefix-match" / shortest-first-drop
+    /// decisions `match_arg`'s own construction makes.
+    fn term_char_len(term: &FtsTerm<'_>) -> usize {
+        match term {
+            FtsTerm::Word(word) | FtsTerm::Negative(word) => word.len(),
+            FtsTerm::Phrase(words) => words.iter().map(|word| word.len()).sum(),
+        }
+    }
+
+    fn append_exclusions(
+        mut match_arg: String,
+        negative_terms: &[&FtsTerm<'_>],
+        synonyms: &SynonymTable,
+    ) -> String {
+        for negative_term in negative_terms {
+            match_arg.push_str(" NOT ");
+            match_arg.push_str(&Self::quoted_term(negative_term, false, synonyms));
+        }
+        match_arg
+    }
+
+    /// Quote a single FTS term, optionally applying the prefix-match `*` and
+    /// expanding a [`FtsTerm::Word`] found in `synonyms` into an `OR` group.
+    fn quoted_term(term: &FtsTerm<'_>, apply_prefix: bool, synonyms: &SynonymTable) -> String {
+        match term {
+            FtsTerm::Negative(word) => format!(r#""{word}""#),
+            // A phrase's prefix match applies to its last word, and has to
+            // go inside the closing quote (`"new bal*"`): appending it after
+            // the quote like a bare term would (`"new bal"*`) isn't valid
+            // FTS5 syntax.
+            FtsTerm::Phrase(words) => {
+                let joined = words.join(" ");
+                if apply_prefix {
+                    format!(r#""{joined}*""#)
+                } else {
+                    format!(r#""{joined}""#)
+                }
+            }
+            FtsTerm::Word(word) => {
+                let primary = if apply_prefix {
+                    format!(r#""{word}"*"#)
+                } else {
+                    format!(r#""{word}""#)
+                };
+                match synonyms.get(&word.to_lowercase()) {
+                    Some(alternatives) if !alternatives.is_empty() => {
+                        let mut group = vec![primary];
+                        group.extend(alternatives.iter().map(|alt| format!(r#""{alt}""#)));
+                        format!("({})", group.join(" OR "))
+                    }
+                    _ => primary,
+                }
+            }
+        }
+    }
+
+    /// Flatten `terms` to the words a title is expected to actually contain:
+    /// phrases become their constituent words, and excluded (`Negative`)
+    /// words are dropped. Also returns, parallel to the flattened words,
+    /// each word's synonym alternatives (empty if it has none or is part of
+    /// a phrase), for [`Self::match_required_stemming`] to consult.
+    fn flatten_positive_words(
+        terms: &[FtsTerm<'a>],
+        synonyms: &SynonymTable,
+    ) -> (Vec<&'a str>, Vec<Vec<String>>) {
+        let mut keywords = Vec::new();
+        let mut keyword_synonyms = Vec::new();
+        for term in terms {
+            match term {
+                FtsTerm::Word(word) => {
+                    keywords.push(*word);
+                    keyword_synonyms.push(
+                        synonyms
+                            .get(&word.to_lowercase())
+                            .cloned()
+                            .unwrap_or_default(),
+                    );
+                }
+                FtsTerm::Phrase(words) => {
+                    keywords.extend(words.iter().copied());
+                    keyword_synonyms.extend(words.iter().map(|_| Vec::new()));
+                }
+                FtsTerm::Negative(_) => {}
+            }
+        }
+        (keywords, keyword_synonyms)
+    }
+
+    /// Parse `keyword` into [`FtsTerm`]s, treating each balanced pair of `"`
+    /// as a phrase and each `-`-prefixed word outside a phrase as an
+    /// exclusion. An unterminated (odd) trailing `"` is left for
+    /// [`Self::split_terms`] to discard like any other stripped character,
+    /// rather than treating the rest of the keyword as one giant phrase.
+    fn parse_fts_terms(keyword: &str) -> Vec<FtsTerm<'_>> {
+        let quote_indices: Vec<usize> = keyword.match_indices('"').map(|(i, _)| i).collect();
+        let mut terms = Vec::new();
+        let mut cursor = 0;
+        for pair in quote_indices.chunks_exact(2) {
+            let (open, close) = (pair[0], pair[1]);
+            terms.extend(
+                Self::split_terms(&keyword[cursor..open])
+                    .into_iter()
+                    .map(FtsTerm::from_token),
+            );
+            let phrase_words = Self::split_terms(&keyword[open + 1..close]);
+            match phrase_words.len() {
+                0 => {}
+                1 => terms.push(FtsTerm::from_token(phrase_words[0])),
+                _ => terms.push(FtsTerm::Phrase(phrase_words)),
+            }
+            cursor = close + 1;
+        }
+        terms.extend(
+            Self::split_terms(&keyword[cursor..])
+                .into_iter()
+                .map(FtsTerm::from_token),
+        );
+        terms
+    }
+
     /// Try to figure out if a FTS match required stemming
     ///
     /// To test this, we have to try to mimic the SQLite FTS logic. This code doesn't do it
     /// perfectly, but it should return the correct result most of the time.
+    ///
+    /// A synonym-expanded term (see [`Self::new_with_synonyms`]) is treated
+    /// as matched, with no stemming required, if any of its alternative
+    /// phrasings appears in `title`.
     pub fn match_required_stemming(&self, title: &str) -> bool {
         let title = title.to_lowercase();
         let split_title = Self::split_terms(&title);
 
         !self.keyword_terms.iter().enumerate().all(|(i, keyword)| {
+            let last_keyword = i == self.keyword_terms.len() - 1;
             split_title.iter().any(|title_word| {
-                let last_keyword = i == self.keyword_terms.len() - 1;
-
                 if last_keyword && self.is_prefix_query {
                     title_word.starts_with(keyword)
                 } else {
                     title_word == keyword
                 }
-            })
+            }) || self.keyword_synonyms[i]
+                .iter()
+                .any(|alternative| title.contains(alternative.as_str()))
         })
     }
 
+    /// Like [`Self::match_required_stemming`], but tolerant of typos.
+    ///
+    /// Builds a Levenshtein automaton per keyword term and walks it against
+    /// every word in `title`, taking the closest word for each term. Returns
+    /// the worst (largest) per-term edit distance found, so the caller can
+    /// rank an exact match ahead of a one-typo match ahead of a two-typo
+    /// match; returns `None` if any term has no word in `title` within
+    /// [`MAX_TYPO_DISTANCE`] edits, i.e. the title doesn't match at all.
+    pub fn match_distance(&self, title: &str) -> Option<u8> {
+        let title = title.to_lowercase();
+        let split_title = Self::split_terms(&title);
+        let builder = LevenshteinAutomatonBuilder::new(MAX_TYPO_DISTANCE as u8, true);
+
+        self.keyword_terms
+            .iter()
+            .enumerate()
+            .map(|(i, keyword)| {
+                let last_keyword = i == self.keyword_terms.len() - 1;
+                let dfa = if last_keyword && self.is_prefix_query {
+                    builder.build_prefix_dfa(keyword)
+                } else {
+                    builder.build_dfa(keyword)
+                };
+                split_title
+                    .iter()
+                    .filter_map(|title_word| match dfa.eval(title_word) {
+                        Distance::Exact(distance) => Some(distance as u8),
+                        Distance::AtLeast(_) => None,
+                    })
+                    .min()
+            })
+            .try_fold(0u8, |worst, term_distance| {
+                term_distance.map(|distance| worst.max(distance))
+            })
+    }
+
+    /// Build the sequence of FTS match arguments to try, in order, for
+    /// `strategy`.
+    ///
+    /// The first candidate is always [`Self::match_arg`] (matching every
+    /// term); callers should try each candidate in turn and stop at the
+    /// first one that returns results. [`TermsMatchingStrategy::All`]
+    /// returns just that one candidate.
+    pub fn match_arg_candidates(&self, strategy: TermsMatchingStrategy) -> Vec<String> {
+        let mut candidates = vec![self.match_arg.clone()];
+        let positive_count = self.positive_quoted.len();
+        let drop_order: Vec<usize> = match strategy {
+            TermsMatchingStrategy::All => return candidates,
+            TermsMatchingStrategy::Last => (1..positive_count).rev().collect(),
+            TermsMatchingStrategy::Frequency => {
+                let mut indices: Vec<usize> = (0..positive_count).collect();
+                indices.sort_by_key(|&i| self.positive_lens[i]);
+                indices
+            }
+        };
+
+        // Only positive (non-`Negative`) terms are ever dropped here: a
+        // `TermsMatchingStrategy` fallback relaxes which words a title has
+        // to contain, it never starts allowing a title to contain an
+        // excluded word, so every `negative_quoted` entry is re-appended to
+        // every candidate below.
+        let mut remaining: Vec<usize> = (0..positive_count).collect();
+        for drop in drop_order {
+            if remaining.len() <= 1 {
+                break;
+            }
+            remaining.retain(|&i| i != drop);
+            candidates.push(Self::build_match_arg(
+                &remaining,
+                &self.positive_quoted,
+                &self.positive_lens,
+                &self.negative_quoted,
+                self.keyword_ends_in_whitespace,
+            ));
+        }
+        candidates
+    }
+
+    /// Join the positive terms at `remaining` (indices into `positive_quoted`
+    /// / `positive_lens`) into an FTS match argument, applying the prefix
+    /// match to whichever of them is last, then append every exclusion in
+    /// `negative_quoted` — the same `FtsTerm`-aware construction
+    /// [`Self::new_with_synonyms`] uses for `match_arg` itself, just over a
+    /// (possibly term-dropped) subset of the original positive terms.
+    fn build_match_arg(
+        remaining: &[usize],
+        positive_quoted: &[(String, String)],
+        positive_lens: &[usize],
+        negative_quoted: &[String],
+        ends_in_whitespace: bool,
+    ) -> String {
+        let total_chars: usize = remaining.iter().map(|&i| positive_lens[i]).sum();
+        let apply_prefix = total_chars > 3 && !ends_in_whitespace;
+        let last = remaining.last().copied();
+
+        let mut match_arg = remaining
+            .iter()
+            .map(|&i| {
+                let (plain, prefixed) = &positive_quoted[i];
+                if apply_prefix && Some(i) == last {
+                    prefixed.clone()
+                } else {
+                    plain.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        for negative in negative_quoted {
+            match_arg.push_str(" NOT ");
+            match_arg.push_str(negative);
+        }
+        match_arg
+    }
+
     fn split_terms(phrase: &str) -> Vec<&str> {
         phrase
             .split([' ', '(', ')', ':', '^', '*', '"', ','])
             .filter(|s| !s.is_empty())
             .collect()
     }
+
+    /// Find the byte range of each keyword term's match in `title`, for the
+    /// address bar to bold when displaying the suggestion.
+    ///
+    /// Returns one [`MatchBounds`] per keyword term that has a matching,
+    /// not-yet-claimed word in `title`; a term with no match (e.g. one that
+    /// only matched due to FTS stemming) is simply omitted rather than
+    /// guessed at. Terms are matched longest-first, so a longer term claims
+    /// its word before a shorter one can; and since each title word can be
+    /// claimed only once, a repeated keyword term (`"bar bar"` against a
+    /// title with a single `"bar"`) claims at most one occurrence of the
+    /// word instead of reporting the same bounds twice. Results are returned in the order they appear in
+    /// `title`, with any bounds that end up overlapping merged into a
+    /// single span (in practice this can't happen today, since a claimed
+    /// word is never considered for a later term, but it keeps the
+    /// contract — disjoint, title-ordered spans — honest against future
+    /// matching strategies that might relax that).
+    pub fn compute_match_bounds(&self, title: &str) -> Vec<MatchBounds> {
+        // Like `match_required_stemming`, this assumes `title.to_lowercase()`
+        // doesn't change the byte length of any word, which holds for the
+        // text we index in practice but isn't true in general (a handful of
+        // Unicode casing rules expand a character's UTF-8 length).
+        let lower_title = title.to_lowercase();
+        let title_words = Self::split_terms_with_positions(&lower_title);
+
+        // Process longer terms first: a longer, more specific term should
+        // get first pick of the title word it matches, rather than a
+        // shorter term (or a duplicate of the same term) claiming it first.
+        let mut term_order: Vec<usize> = (0..self.keyword_terms.len()).collect();
+        term_order.sort_by_key(|&i| core::cmp::Reverse(self.keyword_terms[i].len()));
+
+        let mut claimed = vec![false; title_words.len()];
+        let mut bounds = Vec::new();
+        for i in term_order {
+            let keyword = self.keyword_terms[i];
+            let last_keyword = i == self.keyword_terms.len() - 1;
+            let found = title_words
+                .iter()
+                .enumerate()
+                .find(|&(word_index, &(_, word))| {
+                    !claimed[word_index]
+                        && if last_keyword && self.is_prefix_query {
+                            word.starts_with(keyword)
+                        } else {
+                            word == keyword
+                        }
+                });
+            if let Some((word_index, &(start, word))) = found {
+                claimed[word_index] = true;
+                bounds.push(MatchBounds {
+                    start,
+                    length: word.len(),
+                });
+            }
+        }
+
+        bounds.sort_by_key(|bounds| bounds.start);
+        let mut merged: Vec<MatchBounds> = Vec::new();
+        for bounds in bounds {
+            match merged.last_mut() {
+                Some(last) if bounds.start <= last.start + last.length => {
+                    let end = (bounds.start + bounds.length).max(last.start + last.length);
+                    last.length = end - last.start;
+                }
+                _ => merged.push(bounds),
+            }
+        }
+        merged
+    }
+
+    /// Like [`Self::split_terms`], but also returns each term's starting
+    /// byte offset in `phrase`.
+    fn split_terms_with_positions(phrase: &str) -> Vec<(usize, &str)> {
+        let mut terms = Vec::new();
+        let mut start = None;
+        for (i, c) in phrase.char_indices() {
+            if [' ', '(', ')', ':', '^', '*', '"', ','].contains(&c) {
+                if let Some(term_start) = start.take() {
+                    terms.push((term_start, &phrase[term_start..i]));
+                }
+            } else if start.is_none() {
+                start = Some(i);
+            }
+        }
+        if let Some(term_start) = start {
+            terms.push((term_start, &phrase[term_start..]));
+        }
+        terms
+    }
+}
+
+/// The byte range of a keyword term's match within a suggestion's title, so
+/// the address bar can bold the matched portion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, uniffi::Record)]
+pub struct MatchBounds {
+    pub start: usize,
+    pub length: usize,
 }
 
 /// Given a list of full keywords, create an FTS string to match against.
@@ -306,6 +827,232 @@ mod test {
         assert!(FtsQuery::new("run").match_required_stemming("running shoes"));
     }
 
+    #[test]
+    fn test_fts_query_phrase() {
+        // A balanced quoted region becomes a single adjacency phrase, not
+        // two separately-AND'd terms
+        check_fts_query(r#""new balance" shoes"#, r#""new balance" "shoes"*"#);
+        // Prefix matching a trailing phrase puts the `*` inside the quotes
+        check_fts_query(r#""new bal""#, r#""new bal*""#);
+        // A single-word quoted region behaves just like an unquoted word
+        check_fts_query(r#""shoes""#, r#""shoes"*"#);
+        // An unterminated quote is stripped like any other special char,
+        // not treated as an open-ended phrase
+        check_fts_query(r#""new balance shoes"#, r#""new" "balance" "shoes"*"#);
+    }
+
+    #[test]
+    fn test_fts_query_negative_keyword() {
+        // A `-`-prefixed word is excluded via `NOT`, and doesn't count
+        // towards the prefix-match char total or `keyword_terms`
+        check_parse_keywords("shoes -running", vec!["shoes"]);
+        check_fts_query("shoes -running", r#""shoes"* NOT "running""#);
+        // A negative term after a phrase still gets its own `NOT` clause,
+        // and doesn't affect where the phrase's prefix `*` goes
+        check_fts_query(
+            r#""new balance" -running"#,
+            r#""new balance*" NOT "running""#,
+        );
+        // A quoted `-foo` is still parsed as a negative term, not a literal
+        // phrase containing a hyphen
+        check_fts_query(r#""-foo" bar"#, r#""bar" NOT "foo""#);
+        // A query consisting only of negative terms has no positive term for
+        // `NOT` to apply to, so it falls back to the empty-match behavior
+        // rather than emitting an invalid standalone `NOT` clause
+        check_parse_keywords("-running", vec![]);
+        check_fts_query("-running", r#""""#);
+    }
+
+    fn synonym_table(entries: &[(&str, &[&str])]) -> SynonymTable {
+        entries
+            .iter()
+            .map(|&(term, alternatives)| {
+                (
+                    term.to_string(),
+                    alternatives.iter().map(|alt| alt.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fts_query_synonyms() {
+        let synonyms = synonym_table(&[("nyc", &["new york city"])]);
+        // A term with a synonym becomes an `OR` group of itself and each
+        // alternative, quoted as an adjacency phrase
+        assert_eq!(
+            FtsQuery::new_with_synonyms("nyc pizza", &synonyms).match_arg,
+            r#"("nyc" OR "new york city") "pizza"*"#
+        );
+        // The prefix `*` only applies to the last user-typed term, never to
+        // an injected synonym phrase
+        assert_eq!(
+            FtsQuery::new_with_synonyms("pizza nyc", &synonyms).match_arg,
+            r#""pizza" ("nyc"* OR "new york city")"#
+        );
+        // A term with no synonym entry is left unexpanded
+        assert_eq!(
+            FtsQuery::new_with_synonyms("chicago pizza", &synonyms).match_arg,
+            r#""chicago" "pizza"*"#
+        );
+        // `match_required_stemming` treats a title containing the
+        // alternative phrase as matched, with no stemming required
+        assert!(!FtsQuery::new_with_synonyms("nyc pizza", &synonyms)
+            .match_required_stemming("new york city pizza"));
+    }
+
+    #[test]
+    fn test_fts_query_match_distance() {
+        // Exact matches have a distance of 0
+        assert_eq!(
+            FtsQuery::new("running shoes").match_distance("running shoes"),
+            Some(0)
+        );
+        // One typo in one term still matches, with a non-zero distance
+        assert_eq!(
+            FtsQuery::new("runing shoes").match_distance("running shoes"),
+            Some(1)
+        );
+        // The worst term distance wins, even if other terms match exactly
+        assert_eq!(
+            FtsQuery::new("running shoez").match_distance("running shoes"),
+            Some(1)
+        );
+        // A prefix query only needs the last term to match as a prefix
+        assert_eq!(
+            FtsQuery::new("running sh").match_distance("running shoes"),
+            Some(0)
+        );
+        // Too many edits away from every word in the title: no match
+        assert_eq!(
+            FtsQuery::new("zzzzzzzzzz").match_distance("running shoes"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fts_query_match_arg_candidates() {
+        // `All` never drops a term
+        assert_eq!(
+            FtsQuery::new("new balance running shoes")
+                .match_arg_candidates(TermsMatchingStrategy::All),
+            vec![r#""new" "balance" "running" "shoes"*"#]
+        );
+        // `Last` drops terms from the end, one at a time
+        assert_eq!(
+            FtsQuery::new("new balance running shoes")
+                .match_arg_candidates(TermsMatchingStrategy::Last),
+            vec![
+                r#""new" "balance" "running" "shoes"*"#,
+                r#""new" "balance" "running"*"#,
+                r#""new" "balance"*"#,
+                r#""new""#,
+            ]
+        );
+        // `Frequency` drops the shortest term first
+        assert_eq!(
+            FtsQuery::new("new balance running shoes")
+                .match_arg_candidates(TermsMatchingStrategy::Frequency),
+            vec![
+                r#""new" "balance" "running" "shoes"*"#,
+                r#""balance" "running" "shoes"*"#,
+                r#""balance" "running"*"#,
+                r#""running"*"#,
+            ]
+        );
+        // A single-term keyword has nothing left to drop
+        assert_eq!(
+            FtsQuery::new("shoes").match_arg_candidates(TermsMatchingStrategy::Last),
+            vec![r#""shoes"*"#]
+        );
+        // A `Negative` term is never dropped, and every fallback candidate
+        // re-appends it as a `NOT` exclusion
+        assert_eq!(
+            FtsQuery::new("running shoes -nike")
+                .match_arg_candidates(TermsMatchingStrategy::Last),
+            vec![
+                r#""running" "shoes"* NOT "nike""#,
+                r#""running"* NOT "nike""#,
+            ]
+        );
+        // A `Phrase` is dropped or kept as a whole, never split into its
+        // constituent words
+        assert_eq!(
+            FtsQuery::new(r#""new balance" running shoes"#)
+                .match_arg_candidates(TermsMatchingStrategy::Last),
+            vec![
+                r#""new balance" "running" "shoes"*"#,
+                r#""new balance" "running"*"#,
+                // The phrase's prefix `*` goes inside the closing quote,
+                // after its last word, the same as in `match_arg` itself
+                r#""new balance*""#,
+            ]
+        );
+        // A synonym-expanded term's `OR` group survives into every fallback
+        // candidate it appears in
+        let synonyms = synonym_table(&[("nyc", &["new york city"])]);
+        assert_eq!(
+            FtsQuery::new_with_synonyms("nyc pizza", &synonyms)
+                .match_arg_candidates(TermsMatchingStrategy::Last),
+            vec![
+                r#"("nyc" OR "new york city") "pizza"*"#,
+                // `"nyc"` alone is only 3 characters, too short for a
+                // prefix match, so the surviving candidate keeps the
+                // `OR` group but drops the `*`
+                r#"("nyc" OR "new york city")"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fts_query_compute_match_bounds() {
+        assert_eq!(
+            FtsQuery::new("running shoes").compute_match_bounds("new balance running shoes"),
+            vec![
+                MatchBounds {
+                    start: 12,
+                    length: 7
+                },
+                MatchBounds {
+                    start: 20,
+                    length: 5
+                },
+            ]
+        );
+        // A prefix query's last term matches via `starts_with`, so the
+        // bounds cover the whole title word, not just the typed prefix
+        assert_eq!(
+            FtsQuery::new("running sh").compute_match_bounds("running shoes"),
+            vec![
+                MatchBounds { start: 0, length: 7 },
+                MatchBounds { start: 8, length: 5 },
+            ]
+        );
+        // A term with no matching word in the title is simply omitted
+        assert_eq!(
+            FtsQuery::new("running laces").compute_match_bounds("running shoes"),
+            vec![MatchBounds { start: 0, length: 7 }]
+        );
+        // A repeated keyword term only matching one title word claims that
+        // word once, rather than reporting duplicate identical bounds
+        assert_eq!(
+            FtsQuery::new("bar bar").compute_match_bounds("bar"),
+            vec![MatchBounds { start: 0, length: 3 }]
+        );
+        // The longest term is matched first, so a longer, more specific
+        // term claims its word before a shorter term can steal it
+        assert_eq!(
+            FtsQuery::new("balance new").compute_match_bounds("new balance shoes"),
+            vec![
+                MatchBounds { start: 0, length: 3 },
+                MatchBounds {
+                    start: 4,
+                    length: 7
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_full_keywords_to_fts_content() {
         check_full_keywords_to_fts_content(["a", "b", "c"], "a b c");
@@ -36,7 +36,49 @@ pub(super) struct State {
     push_constant_descs: ArrayVec<super::PushConstantDesc, { super::MAX_PUSH_CONSTANT_COMMANDS }>,
     // The current state of the push constant data block.
     current_push_constant_data: [u32; super::MAX_PUSH_CONSTANTS],
+    // Accumulates the word range touched by `set_push_constants` calls since
+    // the last flush, so back-to-back calls (one per shader stage, say)
+    // coalesce into a single re-upload of each affected uniform instead of
+    // one per call.
+    dirty_push_constant_words: Option<Range<u32>>,
     end_of_pass_timestamp: Option<glow::Query>,
+    // Unlike a timestamp, a pipeline-statistics query counts events across
+    // the whole pass, so it has to be bracketed with `glBeginQuery` at the
+    // start and `glEndQuery` at the end rather than written at either edge.
+    pipeline_statistics_query: Option<(glow::Query, u32)>,
+    // The attachment set the framebuffer is currently bound to, so a
+    // render pass using the exact same set of attachments as the previous
+    // one can reuse it instead of tearing it down and rebuilding it.
+    bound_framebuffer: Option<FramebufferKey>,
+}
+
+/// The set of attachments a framebuffer object is bound to, used to decide
+/// whether [`begin_render_pass`](crate::CommandEncoder::begin_render_pass)
+/// can skip re-issuing bind commands for the framebuffer left bound by the
+/// previous render pass in this same encoder (this is a within-encoder
+/// redundant-bind check, not a device-level object cache: the GL framebuffer
+/// object itself is still rebuilt from `C::ResetFramebuffer`/`C::BindAttachment`
+/// commands on a miss, and nothing here is shared or invalidated across
+/// encoders — `begin_encoding` resets `State` to its default, which drops
+/// any `bound_framebuffer` left over from the previous encoding).
+///
+/// Scoping the cache to a single encoder like this also means no explicit
+/// invalidate-on-drop hook is needed for a referenced texture/view: nothing
+/// can drop a texture this key still points to without first finishing (or
+/// discarding) the encoding that's using it, which already clears the key.
+///
+/// Each color slot keeps its own `Option`, so a sparse MRT set (say, only
+/// attachment 2 bound) stays distinguishable from a different sparse set or
+/// from no attachments at all — collapsing gaps would make unrelated
+/// attachment sets compare equal and reuse the wrong bindings.
+///
+/// A color slot is keyed on its view together with its `depth_slice`, since
+/// two passes on different slices of one 3D view bind different layers.
+#[derive(Clone, PartialEq, Default)]
+struct FramebufferKey {
+    color_attachments:
+        ArrayVec<Option<(super::TextureView, Option<u32>)>, { crate::MAX_COLOR_ATTACHMENTS }>,
+    depth_stencil_attachment: Option<super::TextureView>,
 }
 
 impl Default for State {
@@ -64,16 +106,32 @@ impl Default for State {
             first_instance_location: Default::default(),
             push_constant_descs: Default::default(),
             current_push_constant_data: [0; super::MAX_PUSH_CONSTANTS],
+            dirty_push_constant_words: Default::default(),
             end_of_pass_timestamp: Default::default(),
+            pipeline_statistics_query: Default::default(),
+            bound_framebuffer: Default::default(),
         }
     }
 }
 
+/// Scratch `data_bytes` capacity above which a recycled [`CommandBuffer`](super::CommandBuffer)
+/// is shrunk instead of kept at size: an outlier frame (a huge debug marker,
+/// an unusually large push-constant block) shouldn't make every future frame
+/// pay for that allocation forever.
+const MAX_RETAINED_SCRATCH_BYTES: usize = 1 << 16;
+
+/// How many finished command buffers `reset_all` keeps in
+/// [`CommandEncoder::free_command_buffers`](super::CommandEncoder): enough to
+/// cover typical double/triple buffering without letting an idle encoder's
+/// pool grow without bound.
+const MAX_FREE_COMMAND_BUFFERS: usize = 4;
+
 impl super::CommandBuffer {
     fn clear(&mut self) {
         self.label = None;
         self.commands.clear();
         self.data_bytes.clear();
+        self.data_bytes.shrink_to(MAX_RETAINED_SCRATCH_BYTES);
         self.queries.clear();
     }
 
@@ -204,6 +262,8 @@ impl super::CommandEncoder {
     }
 
     fn prepare_draw(&mut self, first_instance: u32) {
+        self.flush_push_constants();
+
         // If we support fully featured instancing, we want to bind everything as normal
         // and let the draw call sort it out.
         let emulated_first_instance_value = if self
@@ -261,6 +321,10 @@ impl crate::CommandEncoder for super::CommandEncoder {
 
     unsafe fn begin_encoding(&mut self, label: crate::Label) -> Result<(), crate::DeviceError> {
         self.state = State::default();
+        // Reuse a previously-finished command buffer's `Vec` allocations
+        // rather than starting from empty ones every time; `reset_all`
+        // is what keeps this pool populated.
+        self.cmd_buffer = self.free_command_buffers.pop().unwrap_or_default();
         self.cmd_buffer.label = label.map(String::from);
         Ok(())
     }
@@ -270,8 +334,17 @@ impl crate::CommandEncoder for super::CommandEncoder {
     unsafe fn end_encoding(&mut self) -> Result<super::CommandBuffer, crate::DeviceError> {
         Ok(mem::take(&mut self.cmd_buffer))
     }
-    unsafe fn reset_all<I>(&mut self, _command_buffers: I) {
-        //TODO: could re-use the allocations in all these command buffers
+    unsafe fn reset_all<I>(&mut self, command_buffers: I)
+    where
+        I: Iterator<Item = super::CommandBuffer>,
+    {
+        for mut cmd_buffer in command_buffers {
+            if self.free_command_buffers.len() >= MAX_FREE_COMMAND_BUFFERS {
+                break;
+            }
+            cmd_buffer.clear();
+            self.free_command_buffers.push(cmd_buffer);
+        }
     }
 
     unsafe fn transition_buffers<'a, T>(&mut self, barriers: T)
@@ -284,14 +357,16 @@ impl crate::CommandEncoder for super::CommandEncoder {
         {
             return;
         }
+        let mut bits = 0;
         for bar in barriers {
             // GLES only synchronizes storage -> anything explicitly
             if !bar.usage.from.contains(wgt::BufferUses::STORAGE_READ_WRITE) {
                 continue;
             }
-            self.cmd_buffer
-                .commands
-                .push(C::BufferBarrier(bar.buffer.raw.unwrap(), bar.usage.to));
+            bits |= conv::map_buffer_usage_to_barrier_bits(bar.usage.to);
+        }
+        if bits != 0 {
+            self.cmd_buffer.commands.push(C::MemoryBarrier(bits));
         }
     }
 
@@ -306,7 +381,7 @@ impl crate::CommandEncoder for super::CommandEncoder {
             return;
         }
 
-        let mut combined_usage = wgt::TextureUses::empty();
+        let mut bits = 0;
         for bar in barriers {
             // GLES only synchronizes storage -> anything explicitly
             if !bar
@@ -318,13 +393,11 @@ impl crate::CommandEncoder for super::CommandEncoder {
             }
             // unlike buffers, there is no need for a concrete texture
             // object to be bound anywhere for a barrier
-            combined_usage |= bar.usage.to;
+            bits |= conv::map_texture_usage_to_barrier_bits(bar.usage.to);
         }
 
-        if !combined_usage.is_empty() {
-            self.cmd_buffer
-                .commands
-                .push(C::TextureBarrier(combined_usage));
+        if bits != 0 {
+            self.cmd_buffer.commands.push(C::MemoryBarrier(bits));
         }
     }
 
@@ -455,6 +528,15 @@ impl crate::CommandEncoder for super::CommandEncoder {
     }
 
     unsafe fn begin_query(&mut self, set: &super::QuerySet, index: u32) {
+        // Pipeline-statistics targets only exist behind
+        // `GL_ARB_pipeline_statistics_query`, gated by
+        // `PrivateCapabilities::PIPELINE_STATISTICS_QUERY`; `QuerySet`
+        // creation is what's responsible for rejecting an unsupported target
+        // (surfacing it as an unsupported feature rather than letting it
+        // reach here), so by the time we get here `set.target` is known-good
+        // for `self.private_caps`. `QuerySet` creation lives in `device.rs`,
+        // which this snapshot doesn't carry, so that rejection itself isn't
+        // implemented in this tree yet — only this command-recording side is.
         let query = set.queries[index as usize];
         self.cmd_buffer
             .commands
@@ -467,8 +549,21 @@ impl crate::CommandEncoder for super::CommandEncoder {
         let query = set.queries[index as usize];
         self.cmd_buffer.commands.push(C::TimestampQuery(query));
     }
-    unsafe fn reset_queries(&mut self, _set: &super::QuerySet, _range: Range<u32>) {
-        //TODO: what do we do here?
+    unsafe fn reset_queries(&mut self, set: &super::QuerySet, range: Range<u32>) {
+        // `glBeginQuery`/`glEndQuery` implicitly reset a query object's
+        // result, but a query that's never begun again (e.g. the app
+        // recorded a range it never uses this frame) would otherwise keep
+        // reporting its previous value; `glDeleteQueries`+`glGenQueries`
+        // round-trip is what actually clears that without requiring a
+        // dummy begin/end pair.
+        let start = self.cmd_buffer.queries.len();
+        self.cmd_buffer
+            .queries
+            .extend_from_slice(&set.queries[range.start as usize..range.end as usize]);
+        let query_range = start as u32..self.cmd_buffer.queries.len() as u32;
+        self.cmd_buffer
+            .commands
+            .push(C::ResetQueries { query_range });
     }
     unsafe fn copy_query_results(
         &mut self,
@@ -476,7 +571,7 @@ impl crate::CommandEncoder for super::CommandEncoder {
         range: Range<u32>,
         buffer: &super::Buffer,
         offset: wgt::BufferAddress,
-        _stride: wgt::BufferSize,
+        stride: wgt::BufferSize,
     ) {
         let start = self.cmd_buffer.queries.len();
         self.cmd_buffer
@@ -488,6 +583,10 @@ impl crate::CommandEncoder for super::CommandEncoder {
             dst: buffer.clone(),
             dst_target: buffer.target,
             dst_offset: offset,
+            // The caller's stride, not `size_of::<u64>()`: a pipeline-statistics
+            // query packs several `u64` counters per index, so consecutive
+            // results aren't necessarily 8 bytes apart in the destination.
+            dst_stride: stride,
         });
     }
 
@@ -506,6 +605,12 @@ impl crate::CommandEncoder for super::CommandEncoder {
                 .end_of_pass_write_index
                 .map(|index| t.query_set.queries[index as usize]);
         }
+        debug_assert!(self.state.pipeline_statistics_query.is_none());
+        if let Some((set, index)) = desc.pipeline_statistics_query {
+            unsafe { self.begin_query(set, index) }
+            self.state.pipeline_statistics_query =
+                Some((set.queries[index as usize], set.target));
+        }
 
         self.state.render_size = desc.extent;
         self.state.resolve_attachments.clear();
@@ -543,24 +648,71 @@ impl crate::CommandEncoder for super::CommandEncoder {
         {
             // default framebuffer (provided externally)
             Some(&super::TextureInner::DefaultRenderbuffer) => {
+                self.state.bound_framebuffer = None;
                 self.cmd_buffer
                     .commands
                     .push(C::ResetFramebuffer { is_default: true });
             }
             _ => {
-                // set the framebuffer
-                self.cmd_buffer
-                    .commands
-                    .push(C::ResetFramebuffer { is_default: false });
+                let key = FramebufferKey {
+                    color_attachments: desc
+                        .color_attachments
+                        .iter()
+                        .map(|cat| {
+                            cat.as_ref()
+                                .map(|cat| (cat.target.view.clone(), cat.depth_slice))
+                        })
+                        .collect(),
+                    depth_stencil_attachment: desc
+                        .depth_stencil_attachment
+                        .as_ref()
+                        .map(|dsat| dsat.target.view.clone()),
+                };
 
-                for (i, cat) in desc.color_attachments.iter().enumerate() {
-                    if let Some(cat) = cat.as_ref() {
-                        let attachment = glow::COLOR_ATTACHMENT0 + i as u32;
+                // The previous render pass may have left exactly this set of
+                // attachments bound; if so, reuse the framebuffer object
+                // instead of tearing it down and rebuilding it from scratch.
+                if self.state.bound_framebuffer.as_ref() == Some(&key) {
+                    self.cmd_buffer.commands.push(C::BindFramebuffer);
+                } else {
+                    self.cmd_buffer
+                        .commands
+                        .push(C::ResetFramebuffer { is_default: false });
+
+                    for (i, cat) in desc.color_attachments.iter().enumerate() {
+                        if let Some(cat) = cat.as_ref() {
+                            let attachment = glow::COLOR_ATTACHMENT0 + i as u32;
+                            self.cmd_buffer.commands.push(C::BindAttachment {
+                                attachment,
+                                view: cat.target.view.clone(),
+                                depth_slice: cat.depth_slice,
+                            });
+                        }
+                    }
+                    if let Some(ref dsat) = desc.depth_stencil_attachment {
+                        let aspects = dsat.target.view.aspects;
+                        let attachment = match aspects {
+                            crate::FormatAspects::DEPTH => glow::DEPTH_ATTACHMENT,
+                            crate::FormatAspects::STENCIL => glow::STENCIL_ATTACHMENT,
+                            _ => glow::DEPTH_STENCIL_ATTACHMENT,
+                        };
                         self.cmd_buffer.commands.push(C::BindAttachment {
                             attachment,
-                            view: cat.target.view.clone(),
-                            depth_slice: cat.depth_slice,
+                            view: dsat.target.view.clone(),
+                            depth_slice: None,
                         });
+                    }
+
+                    self.state.bound_framebuffer = Some(key);
+                }
+
+                // Resolve and invalidate targets are gathered unconditionally
+                // (even on a cache hit): which attachments need resolving or
+                // invalidating depends on this pass's `ops`, not on whether
+                // the framebuffer object itself changed.
+                for (i, cat) in desc.color_attachments.iter().enumerate() {
+                    if let Some(cat) = cat.as_ref() {
+                        let attachment = glow::COLOR_ATTACHMENT0 + i as u32;
                         if let Some(ref rat) = cat.resolve_target {
                             self.state
                                 .resolve_attachments
@@ -573,16 +725,6 @@ impl crate::CommandEncoder for super::CommandEncoder {
                 }
                 if let Some(ref dsat) = desc.depth_stencil_attachment {
                     let aspects = dsat.target.view.aspects;
-                    let attachment = match aspects {
-                        crate::FormatAspects::DEPTH => glow::DEPTH_ATTACHMENT,
-                        crate::FormatAspects::STENCIL => glow::STENCIL_ATTACHMENT,
-                        _ => glow::DEPTH_STENCIL_ATTACHMENT,
-                    };
-                    self.cmd_buffer.commands.push(C::BindAttachment {
-                        attachment,
-                        view: dsat.target.view.clone(),
-                        depth_slice: None,
-                    });
                     if aspects.contains(crate::FormatAspects::DEPTH)
                         && !dsat.depth_ops.contains(crate::AttachmentOps::STORE)
                     {
@@ -704,6 +846,9 @@ impl crate::CommandEncoder for super::CommandEncoder {
         if let Some(query) = self.state.end_of_pass_timestamp.take() {
             self.cmd_buffer.commands.push(C::TimestampQuery(query));
         }
+        if let Some((_, target)) = self.state.pipeline_statistics_query.take() {
+            self.cmd_buffer.commands.push(C::EndQuery(target));
+        }
     }
 
     unsafe fn set_bind_group(
@@ -805,6 +950,23 @@ impl crate::CommandEncoder for super::CommandEncoder {
         self.state.current_push_constant_data[start_words as usize..end_words as usize]
             .copy_from_slice(data);
 
+        // Don't re-upload affected uniforms yet: `set_push_constants` is
+        // typically called once per shader stage sharing the same backing
+        // memory, so flushing here would re-emit the same uniform several
+        // times in a row. `flush_push_constants` (called from `prepare_draw`
+        // and before dispatch) does the actual `SetPushConstants` emission
+        // once the whole set of calls for this draw/dispatch has landed.
+        self.state.dirty_push_constant_words = Some(match self.state.dirty_push_constant_words {
+            Some(ref dirty) => dirty.start.min(start_words)..dirty.end.max(end_words),
+            None => start_words..end_words,
+        });
+    }
+
+    fn flush_push_constants(&mut self) {
+        let Some(dirty) = self.state.dirty_push_constant_words.take() else {
+            return;
+        };
+
         // We iterate over the uniform list as there may be multiple uniforms that need
         // updating from the same push constant memory (one for each shader stage).
         //
@@ -815,9 +977,11 @@ impl crate::CommandEncoder for super::CommandEncoder {
             let uniform_start_words = uniform.offset / 4;
             let uniform_end_words = uniform_start_words + uniform_size_words;
 
-            // Is true if any word within the uniform binding was updated
+            // Half-open interval overlap: true if any word within the
+            // uniform binding was touched by a `set_push_constants` call
+            // since the last flush.
             let needs_updating =
-                start_words < uniform_end_words || uniform_start_words <= end_words;
+                dirty.start < uniform_end_words && uniform_start_words < dirty.end;
 
             if needs_updating {
                 let uniform_data = &self.state.current_push_constant_data
@@ -1093,6 +1257,22 @@ impl crate::CommandEncoder for super::CommandEncoder {
         draw_count: u32,
     ) {
         self.prepare_draw(0);
+        // `glMultiDrawArraysIndirect` can't patch in a per-draw base-instance
+        // uniform, so when WebGL's `first_instance_location` emulation is
+        // active we have to fall back to one `glDrawArraysIndirect` per draw.
+        if self.state.first_instance_location.is_none()
+            && self
+                .private_caps
+                .contains(super::PrivateCapabilities::MULTI_DRAW_INDIRECT)
+        {
+            self.cmd_buffer.commands.push(C::MultiDrawIndirect {
+                topology: self.state.topology,
+                indirect_buf: buffer.raw.unwrap(),
+                indirect_offset: offset,
+                draw_count,
+            });
+            return;
+        }
         for draw in 0..draw_count as wgt::BufferAddress {
             let indirect_offset =
                 offset + draw * size_of::<wgt::DrawIndirectArgs>() as wgt::BufferAddress;
@@ -1116,6 +1296,20 @@ impl crate::CommandEncoder for super::CommandEncoder {
             wgt::IndexFormat::Uint16 => glow::UNSIGNED_SHORT,
             wgt::IndexFormat::Uint32 => glow::UNSIGNED_INT,
         };
+        if self.state.first_instance_location.is_none()
+            && self
+                .private_caps
+                .contains(super::PrivateCapabilities::MULTI_DRAW_INDIRECT)
+        {
+            self.cmd_buffer.commands.push(C::MultiDrawIndexedIndirect {
+                topology: self.state.topology,
+                index_type,
+                indirect_buf: buffer.raw.unwrap(),
+                indirect_offset: offset,
+                draw_count,
+            });
+            return;
+        }
         for draw in 0..draw_count as wgt::BufferAddress {
             let indirect_offset =
                 offset + draw * size_of::<wgt::DrawIndexedIndirectArgs>() as wgt::BufferAddress;
@@ -1139,23 +1333,67 @@ impl crate::CommandEncoder for super::CommandEncoder {
     }
     unsafe fn draw_indirect_count(
         &mut self,
-        _buffer: &super::Buffer,
-        _offset: wgt::BufferAddress,
-        _count_buffer: &super::Buffer,
-        _count_offset: wgt::BufferAddress,
-        _max_count: u32,
+        buffer: &super::Buffer,
+        offset: wgt::BufferAddress,
+        count_buffer: &super::Buffer,
+        count_offset: wgt::BufferAddress,
+        max_count: u32,
     ) {
-        unreachable!()
+        if self
+            .private_caps
+            .contains(super::PrivateCapabilities::INDIRECT_PARAMETERS)
+        {
+            self.prepare_draw(0);
+            #[allow(clippy::clone_on_copy)] // False positive when cloning glow::UniformLocation
+            self.cmd_buffer.commands.push(C::MultiDrawIndirectCount {
+                topology: self.state.topology,
+                indirect_buf: buffer.raw.unwrap(),
+                indirect_offset: offset,
+                count_buf: count_buffer.raw.unwrap(),
+                count_offset,
+                max_count,
+                first_instance_location: self.state.first_instance_location.clone(),
+            });
+        } else {
+            // No `ARB_indirect_parameters`: we can't read `count_buffer` at
+            // record time, so fall back to issuing all `max_count` draws;
+            // the device-side count is not applied on this path.
+            unsafe { self.draw_indirect(buffer, offset, max_count) }
+        }
     }
     unsafe fn draw_indexed_indirect_count(
         &mut self,
-        _buffer: &super::Buffer,
-        _offset: wgt::BufferAddress,
-        _count_buffer: &super::Buffer,
-        _count_offset: wgt::BufferAddress,
-        _max_count: u32,
+        buffer: &super::Buffer,
+        offset: wgt::BufferAddress,
+        count_buffer: &super::Buffer,
+        count_offset: wgt::BufferAddress,
+        max_count: u32,
     ) {
-        unreachable!()
+        if self
+            .private_caps
+            .contains(super::PrivateCapabilities::INDIRECT_PARAMETERS)
+        {
+            self.prepare_draw(0);
+            let index_type = match self.state.index_format {
+                wgt::IndexFormat::Uint16 => glow::UNSIGNED_SHORT,
+                wgt::IndexFormat::Uint32 => glow::UNSIGNED_INT,
+            };
+            #[allow(clippy::clone_on_copy)] // False positive when cloning glow::UniformLocation
+            self.cmd_buffer
+                .commands
+                .push(C::MultiDrawIndexedIndirectCount {
+                    topology: self.state.topology,
+                    index_type,
+                    indirect_buf: buffer.raw.unwrap(),
+                    indirect_offset: offset,
+                    count_buf: count_buffer.raw.unwrap(),
+                    count_offset,
+                    max_count,
+                    first_instance_location: self.state.first_instance_location.clone(),
+                });
+        } else {
+            unsafe { self.draw_indexed_indirect(buffer, offset, max_count) }
+        }
     }
     unsafe fn draw_mesh_tasks_indirect_count(
         &mut self,
@@ -1180,6 +1418,12 @@ impl crate::CommandEncoder for super::CommandEncoder {
                 .end_of_pass_write_index
                 .map(|index| t.query_set.queries[index as usize]);
         }
+        debug_assert!(self.state.pipeline_statistics_query.is_none());
+        if let Some((set, index)) = desc.pipeline_statistics_query {
+            unsafe { self.begin_query(set, index) }
+            self.state.pipeline_statistics_query =
+                Some((set.queries[index as usize], set.target));
+        }
 
         if let Some(label) = desc.label {
             let range = self.cmd_buffer.add_marker(label);
@@ -1196,6 +1440,9 @@ impl crate::CommandEncoder for super::CommandEncoder {
         if let Some(query) = self.state.end_of_pass_timestamp.take() {
             self.cmd_buffer.commands.push(C::TimestampQuery(query));
         }
+        if let Some((_, target)) = self.state.pipeline_statistics_query.take() {
+            self.cmd_buffer.commands.push(C::EndQuery(target));
+        }
     }
 
     unsafe fn set_compute_pipeline(&mut self, pipeline: &super::ComputePipeline) {
@@ -1207,9 +1454,11 @@ impl crate::CommandEncoder for super::CommandEncoder {
         if count.contains(&0) {
             return;
         }
+        self.flush_push_constants();
         self.cmd_buffer.commands.push(C::Dispatch(count));
     }
     unsafe fn dispatch_indirect(&mut self, buffer: &super::Buffer, offset: wgt::BufferAddress) {
+        self.flush_push_constants();
         self.cmd_buffer.commands.push(C::DispatchIndirect {
             indirect_buf: buffer.raw.unwrap(),
             indirect_offset: offset,